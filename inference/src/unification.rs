@@ -1,5 +1,6 @@
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Unresolved<T> {
@@ -24,42 +25,169 @@ where
     Equality(TypeVar, TypeVar),
     /// declares that the type of typevar is of the literal.
     IsLiteral(TypeVar, Unresolved<T>),
+    /// declares that the type of typevar must be a
+    /// member of the class, e.g. a type that supports
+    /// addition.
+    HasClass(TypeVar, TypeClass<T>),
+}
+
+/// A class of types that support an operation, such as addition
+/// or printing. The class is checked once the type is resolved,
+/// so it can be declared before the type is known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeClass<T> {
+    pub name: String,
+    pub instances: Vec<Instance<T>>,
+}
+
+/// A type that is a member of a class. The subtypes of a generic
+/// must be members of the classes of the instance, in order. A
+/// generic without classes accepts any subtypes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instance<T> {
+    pub typ: T,
+    pub subtypes: Vec<TypeClass<T>>,
+}
+
+impl<T> TypeClass<T> {
+    /// create a class of the types.
+    pub fn new(name: &str, types: Vec<T>) -> TypeClass<T> {
+        TypeClass {
+            name: name.to_owned(),
+            instances: types
+                .into_iter()
+                .map(|typ| Instance {
+                    typ,
+                    subtypes: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    /// add a generic to the class, whose subtypes
+    /// must be members of the classes.
+    pub fn with_generic(mut self, typ: T, subtypes: Vec<TypeClass<T>>) -> TypeClass<T> {
+        self.instances.push(Instance { typ, subtypes });
+        self
+    }
 }
 
 /// A TypeVar collects assumptions around this variable
 pub type TypeVar = usize;
 
+/// Where a constraint was introduced. Every type records the
+/// origin of the constraint that resolved it, so type errors
+/// can explain both of the conflicting types.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Origin {
+    pub source: Source,
+    pub location: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    /// an expression of the program, e.g. a literal or a call.
+    Expression(String),
+    /// a builtin, which constrains its arguments and result.
+    Builtin(String),
+    /// a type that was declared explicitly.
+    Annotation(String),
+}
+
+impl Origin {
+    pub fn new(source: Source, location: Option<String>) -> Origin {
+        Origin { source, location }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source {
+            Source::Expression(ref source) | Source::Builtin(ref source) => {
+                write!(f, "`{}`", source)?
+            }
+            Source::Annotation(ref source) => write!(f, "the annotation `{}`", source)?,
+        }
+        match self.location {
+            Some(ref location) => write!(f, " at {}", location),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A type scheme is a set of types that are generic over
+/// the type variables that were unresolved when it was
+/// generalized. Each instance of the scheme replaces those
+/// with new type variables, so they can resolve to
+/// different types per instance.
+#[derive(Clone, Debug)]
+pub struct TypeScheme {
+    pub type_vars: Vec<TypeVar>,
+    /// the generic type variables, one per set of
+    /// type variables that are equal.
+    quantified: HashSet<TypeVar>,
+}
+
+impl TypeScheme {
+    /// return true if the scheme has any generic types.
+    pub fn is_generic(&self) -> bool {
+        !self.quantified.is_empty()
+    }
+}
+
 /// The TypeResolver is an iterative resolver
 /// of type variables. The TypeResolver immediately
 /// evaluates conditions and provides results and errors.
+///
+/// Type variables that are equal are merged into a set, using
+/// a union-find forest with path compression and union by rank.
+/// The type and the origin of every set are kept by its root.
 #[derive(Debug)]
 pub struct TypeResolver<T>
 where
     T: Clone + PartialEq + Debug,
 {
-    type_vars: Vec<TypeVar>,
-    /// a counter to monotonically iterate reference
-    /// count.
-    reference_counter: usize,
-    reference_by_typevar: HashMap<TypeVar, usize>,
-    type_by_reference: HashMap<usize, Unresolved<T>>,
+    /// the parent of every type variable. Roots are their own
+    /// parent. Paths are compressed as they are searched, which
+    /// may happen while the resolver is borrowed immutably.
+    parents: Vec<Cell<TypeVar>>,
+    /// an upper bound of the height of the tree of every root.
+    ranks: Vec<u8>,
+    types: HashMap<TypeVar, Unresolved<T>>,
+    /// the origin of the constraint that resolved
+    /// the type of every root.
+    origins: HashMap<TypeVar, Origin>,
+    /// the classes the type of every unresolved root must be
+    /// a member of, along with the origin that declared them.
+    classes: HashMap<TypeVar, Vec<(TypeClass<T>, Option<Origin>)>>,
+    /// the origin of the constraints that are added.
+    origin: Option<Origin>,
 }
 
 impl<T: Clone + PartialEq + Debug> TypeResolver<T> {
     pub fn new() -> TypeResolver<T> {
         TypeResolver {
-            type_vars: vec![],
-            reference_counter: 0,
-            reference_by_typevar: HashMap::new(),
-            type_by_reference: HashMap::new(),
+            parents: vec![],
+            ranks: vec![],
+            types: HashMap::new(),
+            origins: HashMap::new(),
+            classes: HashMap::new(),
+            origin: None,
         }
     }
 
+    /// attribute the constraints added from now on to the
+    /// origin, returning the previous one so it can be restored.
+    pub fn set_origin(&mut self, origin: Option<Origin>) -> Option<Origin> {
+        std::mem::replace(&mut self.origin, origin)
+    }
+
     /// allocate a new type variable,
     /// which can be used in constraint relations.
     pub fn create_type_var(&mut self) -> TypeVar {
-        let var = self.type_vars.len();
-        self.type_vars.push(var.clone());
+        let var = self.parents.len();
+        self.parents.push(Cell::new(var));
+        self.ranks.push(0);
         var
     }
 
@@ -68,149 +196,499 @@ impl<T: Clone + PartialEq + Debug> TypeResolver<T> {
     /// with the new constraint provided.
     pub fn add_constraint(&mut self, c: Constraint<T>) -> Result<(), String> {
         match c {
-            Constraint::Equality(ref l, ref r) => {
-                let reference = match self.reference_by_typevar.get(l) {
-                    Some(left_index) => Some(left_index.clone()),
-                    None => None,
-                };
-                match reference {
-                    None => {
-                        let right_index = self.get_or_create_reference(r);
-                        self.reference_by_typevar.insert(l.clone(), right_index);
-                    }
-                    Some(left_index) => {
-                        let right_reference = match self.reference_by_typevar.get(r) {
-                            Some(right_index) => Some(right_index.clone()),
-                            None => None,
-                        };
-                        match right_reference {
-                            None => {
-                                self.reference_by_typevar
-                                    .insert(r.clone(), left_index.clone());
-                            }
-                            Some(right_index) => {
-                                let left = self.type_by_reference.get(&left_index).unwrap().clone();
-                                let right =
-                                    self.type_by_reference.get(&right_index).unwrap().clone();
-                                let unified_type = self.unify(&left, &right)?;
-                                self.type_by_reference
-                                    .insert(left_index, unified_type.clone());
-                                self.type_by_reference.insert(right_index, unified_type);
-                            }
-                        }
-                    }
+            Constraint::Equality(l, r) => self.union(l, r),
+            Constraint::IsLiteral(type_var, typ) => self.set_type(type_var, typ),
+            Constraint::HasClass(type_var, class) => {
+                let origin = self.origin.clone();
+                self.add_class(type_var, class, origin)
+            }
+        }
+    }
+
+    /// check that the type of the type variable is a member of
+    /// the class. If the type is not resolved yet, the class is
+    /// kept by the root and checked once it is.
+    fn add_class(
+        &mut self,
+        type_var: TypeVar,
+        class: TypeClass<T>,
+        origin: Option<Origin>,
+    ) -> Result<(), String> {
+        let root = self.find(type_var);
+        let (typ, subtypes) = match self.get_root_type(root) {
+            Unresolved::Any => {
+                let classes = self.classes.entry(root).or_insert_with(Vec::new);
+                if !classes.iter().any(|(existing, _)| existing == &class) {
+                    classes.push((class, origin));
                 }
+                return Ok(());
             }
-            Constraint::IsLiteral(ref type_var, ref typ) => {
-                let reference = self.get_or_create_reference(type_var);
-                self.set_type(reference, typ.clone())?;
+            Unresolved::Literal(typ) => (typ, vec![]),
+            Unresolved::Generic(typ, subtypes) => (typ, subtypes),
+        };
+        let instance = match class.instances.iter().find(|instance| instance.typ == typ) {
+            Some(instance) => instance.clone(),
+            None => return Err(self.not_a_member(root, &class, &origin)),
+        };
+        for (subtype, subclass) in subtypes.iter().zip(instance.subtypes) {
+            self.add_class(*subtype, subclass, origin.clone())?;
+        }
+        Ok(())
+    }
+
+    /// check the classes kept by the roots against
+    /// the type they are resolved to.
+    fn check_classes(&mut self, roots: &[TypeVar]) -> Result<(), String> {
+        for root in roots {
+            if let Some(classes) = self.classes.remove(root) {
+                for (class, origin) in classes {
+                    self.add_class(*root, class, origin)?;
+                }
             }
         }
         Ok(())
     }
 
+    /// describe a type that is not a member of a class, e.g.:
+    ///
+    /// expected a type that supports Add because of `+` at main.ds:4, found Bool from `eq` at main.ds:3
+    fn not_a_member(
+        &self,
+        root: TypeVar,
+        class: &TypeClass<T>,
+        class_origin: &Option<Origin>,
+    ) -> String {
+        let mut message = format!("expected a type that supports {}", class.name);
+        if let Some(ref origin) = class_origin {
+            message += &format!(" because of {}", origin);
+        }
+        message += &format!(", found {}", self.describe_types(&[root])[0]);
+        if let Some(ref origin) = self.origins.get(&root) {
+            message += &format!(" from {}", origin);
+        }
+        message
+    }
+
+    /// merge the sets of the type variables, so later constraints
+    /// on either apply to all of their type variables.
+    fn union(&mut self, left: TypeVar, right: TypeVar) -> Result<(), String> {
+        let (left_root, right_root) = (self.find(left), self.find(right));
+        if left_root == right_root {
+            return Ok(());
+        }
+        let left_type = self.get_root_type(left_root);
+        let right_type = self.get_root_type(right_root);
+        if self.occurs(left_root, &right_type) {
+            return Err(self.infinite_type(left_root, &right_type));
+        }
+        if self.occurs(right_root, &left_type) {
+            return Err(self.infinite_type(right_root, &left_type));
+        }
+        let left_origin = self.origins.get(&left_root).cloned();
+        let right_origin = self.origins.get(&right_root).cloned();
+        let unified_type = self.unify(&left_type, &left_origin, &right_type, &right_origin)?;
+        // unifying the subtypes of generics may have
+        // merged the sets already.
+        let (left_root, right_root) = (self.find(left), self.find(right));
+        let root = if left_root == right_root {
+            left_root
+        } else {
+            for root in &[left_root, right_root] {
+                self.types.remove(root);
+                self.origins.remove(root);
+            }
+            self.link(left_root, right_root)
+        };
+        let origin = if left_type == Unresolved::Any {
+            right_origin
+        } else {
+            left_origin
+        };
+        if let Some(origin) = origin {
+            self.origins.insert(root, origin);
+        }
+        self.types.insert(root, unified_type);
+        self.check_classes(&[left_root, right_root])
+    }
+
+    /// attach the root of the lower tree to the other
+    /// root, returning the root of the merged set.
+    fn link(&mut self, left_root: TypeVar, right_root: TypeVar) -> TypeVar {
+        let (child, root) = if self.ranks[left_root] < self.ranks[right_root] {
+            (left_root, right_root)
+        } else {
+            (right_root, left_root)
+        };
+        if self.ranks[child] == self.ranks[root] {
+            self.ranks[root] += 1;
+        }
+        self.parents[child].set(root);
+        root
+    }
+
+    /// find the root of the set of the type variable. Every type
+    /// variable on the path is attached to the root directly.
+    fn find(&self, type_var: TypeVar) -> TypeVar {
+        let mut root = type_var;
+        while self.parents[root].get() != root {
+            root = self.parents[root].get();
+        }
+        let mut current = type_var;
+        while current != root {
+            let parent = self.parents[current].get();
+            self.parents[current].set(root);
+            current = parent;
+        }
+        root
+    }
+
+    /// return true if the set of the root is a part of the type. A
+    /// type variable can not be equal to a type that contains it,
+    /// e.g. a = Array<a>, as the type would be infinite.
+    fn occurs(&self, root: TypeVar, typ: &Unresolved<T>) -> bool {
+        match typ {
+            Unresolved::Generic(_, ref subtypes) => subtypes.iter().any(|subtype| {
+                let subtype = self.find(*subtype);
+                subtype == root || self.occurs(root, &self.get_root_type(subtype))
+            }),
+            _ => false,
+        }
+    }
+
+    /// describe an infinite type, e.g.:
+    ///
+    /// infinite type a = Array<a> from `f` at main.ds:3
+    fn infinite_type(&self, root: TypeVar, typ: &Unresolved<T>) -> String {
+        let mut names = HashMap::new();
+        let mut message = format!(
+            "infinite type {} = {}",
+            self.describe_type(&root, &mut names),
+            self.describe_unresolved(typ, &mut names)
+        );
+        if let Some(ref origin) = self.origin {
+            message += &format!(" from {}", origin);
+        }
+        message
+    }
+
+    /// sets without a type have not been
+    /// constrained to any type yet.
+    fn get_root_type(&self, root: TypeVar) -> Unresolved<T> {
+        match self.types.get(&root) {
+            Some(typ) => typ.clone(),
+            None => Unresolved::Any,
+        }
+    }
+
+    /// generalize the types into a scheme, which is generic over
+    /// all the type variables that are unresolved. Type variables
+    /// that the monomorphic types refer to stay fixed, as they
+    /// can still be constrained.
+    pub fn generalize(&self, type_vars: &[TypeVar], monomorphic: &[TypeVar]) -> TypeScheme {
+        let mut quantified = HashSet::new();
+        for type_var in type_vars {
+            self.free_classes(type_var, &mut quantified);
+        }
+        let mut fixed = HashSet::new();
+        for type_var in monomorphic {
+            self.free_classes(type_var, &mut fixed);
+        }
+        TypeScheme {
+            type_vars: type_vars.to_vec(),
+            quantified: quantified.difference(&fixed).cloned().collect(),
+        }
+    }
+
+    /// create an instance of the scheme, replacing every generic
+    /// type variable with a new one. The types of the instance are
+    /// returned in the same order as the types of the scheme.
+    pub fn instantiate(&mut self, scheme: &TypeScheme) -> Result<Vec<TypeVar>, String> {
+        // the sets may have been merged since the
+        // scheme was generalized.
+        let quantified: HashSet<TypeVar> = scheme
+            .quantified
+            .iter()
+            .map(|type_var| self.find(*type_var))
+            .collect();
+        let mut substitutions = HashMap::new();
+        let mut instance = Vec::with_capacity(scheme.type_vars.len());
+        for type_var in &scheme.type_vars {
+            instance.push(self.instantiate_type_var(&quantified, type_var, &mut substitutions)?);
+        }
+        Ok(instance)
+    }
+
+    fn instantiate_type_var(
+        &mut self,
+        quantified: &HashSet<TypeVar>,
+        type_var: &TypeVar,
+        substitutions: &mut HashMap<TypeVar, TypeVar>,
+    ) -> Result<TypeVar, String> {
+        let class = self.find(*type_var);
+        if let Some(instance) = substitutions.get(&class) {
+            return Ok(*instance);
+        }
+        if quantified.contains(&class) {
+            let instance = self.create_type_var();
+            substitutions.insert(class, instance);
+            // the instance must be a member of the same classes.
+            if let Some(classes) = self.classes.get(&class).cloned() {
+                for (type_class, origin) in classes {
+                    self.add_class(instance, type_class, origin)?;
+                }
+            }
+            return Ok(instance);
+        }
+        // only generics can contain generic type variables.
+        let (typ, subtypes) = match self.get_unresolved(type_var) {
+            Some(Unresolved::Generic(typ, subtypes)) => (typ, subtypes),
+            _ => return Ok(*type_var),
+        };
+        let mut instance_subtypes = Vec::with_capacity(subtypes.len());
+        for subtype in &subtypes {
+            instance_subtypes.push(self.instantiate_type_var(
+                quantified,
+                subtype,
+                substitutions,
+            )?);
+        }
+        let instance = if instance_subtypes == subtypes {
+            *type_var
+        } else {
+            // the instance shares the origin of the generic.
+            let instance = self.create_type_var();
+            let origin = self.get_origin(type_var);
+            let previous = self.set_origin(origin);
+            let result = self.add_constraint(Constraint::IsLiteral(
+                instance,
+                Unresolved::Generic(typ, instance_subtypes),
+            ));
+            self.set_origin(previous);
+            result?;
+            instance
+        };
+        substitutions.insert(class, instance);
+        Ok(instance)
+    }
+
+    /// describe the types of the type variables. Unresolved types
+    /// are named with letters, in the order they appear in, so
+    /// type variables that are equal share the same name.
+    pub fn describe_types(&self, type_vars: &[TypeVar]) -> Vec<String> {
+        let mut names = HashMap::new();
+        type_vars
+            .iter()
+            .map(|type_var| self.describe_type(type_var, &mut names))
+            .collect()
+    }
+
+    /// describe the classes that the unresolved types of the type
+    /// variables must be members of, e.g. "a: Add". The types are
+    /// named as describe_types names them.
+    pub fn describe_classes(&self, type_vars: &[TypeVar]) -> Vec<String> {
+        let mut names = HashMap::new();
+        for type_var in type_vars {
+            self.describe_type(type_var, &mut names);
+        }
+        let mut roots: Vec<(&String, &TypeVar)> =
+            names.iter().map(|(root, name)| (name, root)).collect();
+        // the names are given in order, so the shortest come first.
+        roots.sort_by_key(|(name, _)| (name.len(), *name));
+        let mut descriptions = vec![];
+        for (name, root) in roots {
+            let mut class_names: Vec<&str> = vec![];
+            for (class, _) in self.classes.get(root).into_iter().flatten() {
+                if !class_names.contains(&class.name.as_str()) {
+                    class_names.push(&class.name);
+                }
+            }
+            if !class_names.is_empty() {
+                descriptions.push(format!("{}: {}", name, class_names.join(" + ")));
+            }
+        }
+        descriptions
+    }
+
+    fn describe_type(&self, type_var: &TypeVar, names: &mut HashMap<TypeVar, String>) -> String {
+        match self.get_unresolved(type_var) {
+            None | Some(Unresolved::Any) => {
+                let index = names.len();
+                names
+                    .entry(self.find(*type_var))
+                    .or_insert_with(|| type_variable_name(index))
+                    .clone()
+            }
+            Some(typ) => self.describe_unresolved(&typ, names),
+        }
+    }
+
+    fn describe_unresolved(
+        &self,
+        typ: &Unresolved<T>,
+        names: &mut HashMap<TypeVar, String>,
+    ) -> String {
+        match typ {
+            Unresolved::Any => type_variable_name(names.len()),
+            Unresolved::Literal(ref typ) => format!("{:?}", typ),
+            Unresolved::Generic(ref typ, ref subtypes) => {
+                let subtypes: Vec<String> = subtypes
+                    .iter()
+                    .map(|subtype| self.describe_type(subtype, names))
+                    .collect();
+                format!("{:?}<{}>", typ, subtypes.join(", "))
+            }
+        }
+    }
+
+    /// collect the classes of all unresolved type variables
+    /// within the type.
+    fn free_classes(&self, type_var: &TypeVar, classes: &mut HashSet<TypeVar>) {
+        match self.get_unresolved(type_var) {
+            None | Some(Unresolved::Any) => {
+                classes.insert(self.find(*type_var));
+            }
+            Some(Unresolved::Generic(_, ref subtypes)) => {
+                for subtype in subtypes {
+                    self.free_classes(subtype, classes);
+                }
+            }
+            Some(Unresolved::Literal(_)) => {}
+        }
+    }
+
+    fn get_origin(&self, type_var: &TypeVar) -> Option<Origin> {
+        self.origins.get(&self.find(*type_var)).cloned()
+    }
+
+    fn get_unresolved(&self, type_var: &TypeVar) -> Option<Unresolved<T>> {
+        self.types.get(&self.find(*type_var)).cloned()
+    }
+
     /// return true if the type vars are referencing the
     /// same variable.
-    pub fn is_equal(&mut self, l: &TypeVar, r: &TypeVar) -> bool {
-        self.get_or_create_reference(l) == self.get_or_create_reference(r)
+    pub fn is_equal(&self, l: &TypeVar, r: &TypeVar) -> bool {
+        self.find(*l) == self.find(*r)
     }
 
+    /// unify the expected type with the type found, returning
+    /// the unified type. The origins explain each of the types
+    /// if they do not match.
     fn unify(
         &mut self,
-        left: &Unresolved<T>,
-        right: &Unresolved<T>,
+        expected: &Unresolved<T>,
+        expected_origin: &Option<Origin>,
+        found: &Unresolved<T>,
+        found_origin: &Option<Origin>,
     ) -> Result<Unresolved<T>, String> {
-        // edge case to handle right being any.
-        if let &Unresolved::Any = right {
-            return Ok(left.clone());
-        }
-        match left {
-            // Any is the most generic: return the right type if so.
-            Unresolved::Any => Ok(right.clone()),
-            Unresolved::Literal(ref left_type) => {
-                if let Unresolved::Literal(ref right_type) = right {
-                    if left_type == right_type {
-                        return Ok(Unresolved::Literal(left_type.clone()));
+        // edge case to handle found being any.
+        if let &Unresolved::Any = found {
+            return Ok(expected.clone());
+        }
+        match expected {
+            // Any is the most generic: return the found type if so.
+            Unresolved::Any => Ok(found.clone()),
+            Unresolved::Literal(ref expected_type) => {
+                if let Unresolved::Literal(ref found_type) = found {
+                    if expected_type == found_type {
+                        return Ok(Unresolved::Literal(expected_type.clone()));
                     }
                 }
-                return Err(format!(
-                    "unable to unify literal with non-literal: {:?} and {:?}",
-                    left_type, right,
-                ));
+                Err(self.mismatch(expected, expected_origin, found, found_origin))
             }
-            Unresolved::Generic(ref left_type, ref left_subtypes) => match &right {
-                Unresolved::Generic(ref right_type, ref right_subtypes) => {
-                    if left_subtypes.len() != right_subtypes.len() {
-                        return Err(format!(
-                            "unable to unify literal with non-literal {:?} and {:?}",
-                            left_subtypes, right_subtypes
-                        ));
-                    }
-                    if right_type != left_type {
-                        return Err(String::from("generic type mismatch"));
-                    }
-                    for i in 0..left_subtypes.len() {
+            Unresolved::Generic(ref expected_type, ref expected_subtypes) => match &found {
+                Unresolved::Generic(ref found_type, ref found_subtypes)
+                    if found_type == expected_type
+                        && found_subtypes.len() == expected_subtypes.len() =>
+                {
+                    for i in 0..expected_subtypes.len() {
                         self.add_constraint(Constraint::Equality(
-                            left_subtypes[i],
-                            right_subtypes[i],
+                            expected_subtypes[i],
+                            found_subtypes[i],
                         ))?;
                     }
                     Ok(Unresolved::Generic(
-                        left_type.clone(),
-                        left_subtypes.clone(),
+                        expected_type.clone(),
+                        expected_subtypes.clone(),
                     ))
                 }
-                _ => Err(format!(
-                    "i unable to unify literal with non-literal {:?} and {:?}",
-                    left, right
-                )),
+                _ => Err(self.mismatch(expected, expected_origin, found, found_origin)),
             },
         }
     }
 
+    /// describe a type mismatch, e.g.:
+    ///
+    /// expected Int because of `+` at main.ds:4, found Bool from `eq` at main.ds:3
+    fn mismatch(
+        &self,
+        expected: &Unresolved<T>,
+        expected_origin: &Option<Origin>,
+        found: &Unresolved<T>,
+        found_origin: &Option<Origin>,
+    ) -> String {
+        let mut names = HashMap::new();
+        let mut message = format!(
+            "expected {}",
+            self.describe_unresolved(expected, &mut names)
+        );
+        if let Some(ref origin) = expected_origin {
+            message += &format!(" because of {}", origin);
+        }
+        message += &format!(", found {}", self.describe_unresolved(found, &mut names));
+        if let Some(ref origin) = found_origin {
+            message += &format!(" from {}", origin);
+        }
+        message
+    }
+
     /// return the resolved type for the type variable, if it exists.
     /// In the case of a generic, a tuple will be returned with the
     /// list of subtypes, resolved as well.
     pub fn get_type(&self, t: &TypeVar) -> Option<Resolved<T>> {
-        match self.reference_by_typevar.get(t) {
-            Some(reference) => match self.type_by_reference.get(reference) {
-                Some(type_resolver_type) => match type_resolver_type {
-                    Unresolved::Any => None,
-                    Unresolved::Literal(ref t) => Some(Resolved::Literal(t.clone())),
-                    Unresolved::Generic(ref t, ref subtypes) => {
-                        let mut resolved_subtypes = Vec::with_capacity(subtypes.len());
-                        for subtype in subtypes {
-                            match self.get_type(subtype) {
-                                Some(s) => resolved_subtypes.push(s),
-                                None => return None,
-                            }
-                        }
-                        Some(Resolved::Generic(t.clone(), resolved_subtypes))
+        match self.get_unresolved(t) {
+            None | Some(Unresolved::Any) => None,
+            Some(Unresolved::Literal(t)) => Some(Resolved::Literal(t)),
+            Some(Unresolved::Generic(t, subtypes)) => {
+                let mut resolved_subtypes = Vec::with_capacity(subtypes.len());
+                for subtype in &subtypes {
+                    match self.get_type(subtype) {
+                        Some(s) => resolved_subtypes.push(s),
+                        None => return None,
                     }
-                },
-                None => None,
-            },
-            None => None,
+                }
+                Some(Resolved::Generic(t, resolved_subtypes))
+            }
         }
     }
 
-    fn get_or_create_reference(&mut self, t: &TypeVar) -> usize {
-        self.reference_by_typevar
-            .entry(t.clone())
-            .or_insert({
-                self.reference_counter += 1;
-                self.reference_counter
-            })
-            .clone()
+    /// constrain the type variable to the type. The type is expected
+    /// by the current origin, so it is reported as such if the type
+    /// variable has a different type.
+    fn set_type(&mut self, type_var: TypeVar, typ: Unresolved<T>) -> Result<(), String> {
+        let root = self.find(type_var);
+        let existing_type = self.get_root_type(root);
+        if self.occurs(root, &typ) {
+            return Err(self.infinite_type(root, &typ));
+        }
+        let existing_origin = self.origins.get(&root).cloned();
+        let origin = self.origin.clone();
+        let unified_type = self.unify(&typ, &origin, &existing_type, &existing_origin)?;
+        let root = self.find(type_var);
+        if existing_type == Unresolved::Any && typ != Unresolved::Any {
+            if let Some(origin) = origin {
+                self.origins.insert(root, origin);
+            }
+        }
+        self.types.insert(root, unified_type);
+        self.check_classes(&[root])
     }
+}
 
-    fn set_type(&mut self, reference: usize, typ: Unresolved<T>) -> Result<(), String> {
-        let existing_type = match self.type_by_reference.get(&reference) {
-            Some(current_typ) => current_typ.clone(),
-            None => typ.clone(),
-        };
-        let unified_type = self.unify(&existing_type, &typ)?;
-        self.type_by_reference.insert(reference, unified_type);
-        Ok(())
+/// the name of the nth unresolved type: a, b, ..., z, a1, b1, ...
+fn type_variable_name(index: usize) -> String {
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+        0 => letter.to_string(),
+        n => format!("{}{}", letter, n),
     }
 }
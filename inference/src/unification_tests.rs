@@ -102,3 +102,295 @@ fn test_unification_any_parameter() {
         .unwrap();
     assert!(type_resolver.get_type(&a) == Some(Resolved::Literal(ExampleTypes::Bool)));
 }
+
+#[test]
+fn test_instances_of_generic_scheme_are_independent() {
+    let mut type_resolver = TypeResolver::new();
+    // the identity function: a -> a
+    let arg = type_resolver.create_type_var();
+    let result = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::Equality(arg, result))
+        .unwrap();
+    let scheme = type_resolver.generalize(&[arg, result], &[]);
+    assert!(scheme.is_generic());
+    let first = type_resolver.instantiate(&scheme).unwrap();
+    let second = type_resolver.instantiate(&scheme).unwrap();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            first[0],
+            Unresolved::Literal(ExampleTypes::Bool),
+        ))
+        .unwrap();
+    let array = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            second[0],
+            Unresolved::Generic(ExampleTypes::Array, vec![array]),
+        ))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            array,
+            Unresolved::Literal(ExampleTypes::Bool),
+        ))
+        .unwrap();
+    assert_eq!(
+        type_resolver.get_type(&first[1]),
+        Some(Resolved::Literal(ExampleTypes::Bool))
+    );
+    assert_eq!(
+        type_resolver.get_type(&second[1]),
+        Some(Resolved::Generic(
+            ExampleTypes::Array,
+            vec![Resolved::Literal(ExampleTypes::Bool)]
+        ))
+    );
+    // the scheme itself is left generic.
+    assert_eq!(type_resolver.get_type(&arg), None);
+}
+
+#[test]
+fn test_monomorphic_types_are_not_generalized() {
+    let mut type_resolver: TypeResolver<ExampleTypes> = TypeResolver::new();
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    let scheme = type_resolver.generalize(&[a, b], &[b]);
+    let instance = type_resolver.instantiate(&scheme).unwrap();
+    assert!(instance[0] != a);
+    assert_eq!(instance[1], b);
+}
+
+#[test]
+fn test_describe_types() {
+    let mut type_resolver = TypeResolver::new();
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    let c = type_resolver.create_type_var();
+    let d = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            a,
+            Unresolved::Generic(ExampleTypes::Array, vec![b]),
+        ))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::Equality(c, b))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            d,
+            Unresolved::Literal(ExampleTypes::Bool),
+        ))
+        .unwrap();
+    assert_eq!(
+        type_resolver.describe_types(&[a, c, d]),
+        vec!["Array<a>", "a", "Bool"]
+    );
+}
+
+#[test]
+fn test_describe_classes() {
+    let mut type_resolver = TypeResolver::new();
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    let bools = TypeClass::new("Not", vec![ExampleTypes::Bool]);
+    let arrays = TypeClass::new("Len", vec![]).with_generic(ExampleTypes::Array, vec![]);
+    type_resolver
+        .add_constraint(Constraint::HasClass(b, bools))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::HasClass(b, arrays))
+        .unwrap();
+    assert_eq!(
+        type_resolver.describe_classes(&[a, b]),
+        vec!["b: Not + Len"]
+    );
+}
+
+#[test]
+fn test_type_error_describes_origins() {
+    let mut type_resolver = TypeResolver::new();
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    type_resolver.set_origin(Some(Origin::new(
+        Source::Expression(String::from("[]")),
+        Some(String::from("main.ds:3")),
+    )));
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            a,
+            Unresolved::Generic(ExampleTypes::Array, vec![b]),
+        ))
+        .unwrap();
+    type_resolver.set_origin(Some(Origin::new(
+        Source::Builtin(String::from("not")),
+        Some(String::from("main.ds:4")),
+    )));
+    assert_eq!(
+        type_resolver.add_constraint(Constraint::IsLiteral(
+            a,
+            Unresolved::Literal(ExampleTypes::Bool),
+        )),
+        Err(String::from(
+            "expected Bool because of `not` at main.ds:4, found Array<a> from `[]` at main.ds:3"
+        ))
+    );
+}
+
+#[test]
+fn test_equality_is_transitive_across_merged_sets() {
+    let mut type_resolver = TypeResolver::new();
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    let c = type_resolver.create_type_var();
+    let d = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::Equality(a, b))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::Equality(c, d))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::Equality(b, d))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            a,
+            Unresolved::Literal(ExampleTypes::Bool),
+        ))
+        .unwrap();
+    assert!(type_resolver.is_equal(&a, &c));
+    assert_eq!(
+        type_resolver.get_type(&d),
+        Some(Resolved::Literal(ExampleTypes::Bool))
+    );
+}
+
+#[test]
+fn test_long_chains_of_equalities() {
+    let mut type_resolver = TypeResolver::new();
+    let first = type_resolver.create_type_var();
+    let mut previous = first;
+    for _ in 0..100_000 {
+        let next = type_resolver.create_type_var();
+        type_resolver
+            .add_constraint(Constraint::Equality(next, previous))
+            .unwrap();
+        previous = next;
+    }
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            previous,
+            Unresolved::Literal(ExampleTypes::Bool),
+        ))
+        .unwrap();
+    assert_eq!(
+        type_resolver.get_type(&first),
+        Some(Resolved::Literal(ExampleTypes::Bool))
+    );
+}
+
+#[test]
+fn test_occurs_check() {
+    let mut type_resolver = TypeResolver::new();
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            b,
+            Unresolved::Generic(ExampleTypes::Array, vec![a]),
+        ))
+        .unwrap();
+    assert_eq!(
+        type_resolver.add_constraint(Constraint::Equality(a, b)),
+        Err(String::from("infinite type a = Array<a>"))
+    );
+    assert_eq!(
+        type_resolver.add_constraint(Constraint::IsLiteral(
+            a,
+            Unresolved::Generic(ExampleTypes::Array, vec![b]),
+        )),
+        Err(String::from("infinite type a = Array<Array<a>>"))
+    );
+    assert_eq!(type_resolver.get_type(&b), None);
+}
+
+#[test]
+fn test_type_classes() {
+    let mut type_resolver = TypeResolver::new();
+    let bools = TypeClass::new("Not", vec![ExampleTypes::Bool]);
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::HasClass(a, bools.clone()))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::Equality(a, b))
+        .unwrap();
+    assert_eq!(
+        type_resolver.add_constraint(Constraint::IsLiteral(
+            b,
+            Unresolved::Generic(ExampleTypes::Array, vec![a]),
+        )),
+        Err(String::from("infinite type a = Array<a>"))
+    );
+    assert_eq!(
+        type_resolver.add_constraint(Constraint::IsLiteral(
+            b,
+            Unresolved::Literal(ExampleTypes::Any),
+        )),
+        Err(String::from("expected a type that supports Not, found Any"))
+    );
+}
+
+#[test]
+fn test_type_classes_of_generics() {
+    let mut type_resolver = TypeResolver::new();
+    let bools = TypeClass::new("Not", vec![ExampleTypes::Bool]);
+    let arrays = TypeClass::new("Not", vec![]).with_generic(ExampleTypes::Array, vec![bools]);
+    let a = type_resolver.create_type_var();
+    let b = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::HasClass(a, arrays))
+        .unwrap();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            a,
+            Unresolved::Generic(ExampleTypes::Array, vec![b]),
+        ))
+        .unwrap();
+    assert_eq!(
+        type_resolver.add_constraint(Constraint::IsLiteral(
+            b,
+            Unresolved::Literal(ExampleTypes::Any),
+        )),
+        Err(String::from("expected a type that supports Not, found Any"))
+    );
+}
+
+#[test]
+fn test_type_classes_are_instantiated() {
+    let mut type_resolver = TypeResolver::new();
+    let bools = TypeClass::new("Not", vec![ExampleTypes::Bool]);
+    let a = type_resolver.create_type_var();
+    type_resolver
+        .add_constraint(Constraint::HasClass(a, bools))
+        .unwrap();
+    let scheme = type_resolver.generalize(&[a], &[]);
+    let instance = type_resolver.instantiate(&scheme).unwrap();
+    assert!(type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            instance[0],
+            Unresolved::Literal(ExampleTypes::Any),
+        ))
+        .is_err());
+    let instance = type_resolver.instantiate(&scheme).unwrap();
+    type_resolver
+        .add_constraint(Constraint::IsLiteral(
+            instance[0],
+            Unresolved::Literal(ExampleTypes::Bool),
+        ))
+        .unwrap();
+    assert_eq!(type_resolver.get_type(&a), None);
+}
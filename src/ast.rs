@@ -1,14 +1,18 @@
 use super::DispError;
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::rc::Rc;
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq)]
 pub enum Token {
     BangSymbol(Box<String>),
     Boolean(bool),
     Bytes(Box<String>),
     Comment(Box<String>),
     Expression(Vec<Token>),
+    Float(f64),
     Integer(i64),
     List(Vec<Token>),
     // blocks are used to represent
@@ -19,43 +23,153 @@ pub enum Token {
     None,
     Symbol(Box<String>),
     String(Box<String>),
+    // a token, annotated with where it
+    // was written in the source.
+    Located(Box<Location>, Box<Token>),
 }
 
+// float literals are never NaN, so
+// every token is equal to itself.
+impl Eq for Token {}
+
 impl Token {
+    /// return the token without its location.
+    pub fn unlocated(&self) -> &Token {
+        match self {
+            &Token::Located(_, ref token) => token.unlocated(),
+            t => t,
+        }
+    }
+
+    pub fn unlocated_mut(&mut self) -> &mut Token {
+        match self {
+            Token::Located(_, ref mut token) => token.unlocated_mut(),
+            t => t,
+        }
+    }
+
     pub fn to_hashable(&self) -> Result<HashableToken, DispError> {
         match self {
             &Token::Symbol(ref s) => Ok(HashableToken::Symbol(s.clone())),
             &Token::Integer(i) => Ok(HashableToken::Integer(i)),
+            &Token::Float(f) => Ok(HashableToken::Float(f)),
             &Token::Boolean(b) => Ok(HashableToken::Boolean(b)),
             &Token::None => Ok(HashableToken::None),
             t => Err(DispError::new(&format!(
-                "unable to convert token {} to hashable",
+                "{} can not be the key of a map",
                 t
             ))),
         }
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq)]
 pub enum HashableToken {
     Symbol(Box<String>),
     Integer(i64),
+    Float(f64),
     Boolean(bool),
     None,
 }
 
+// float literals are never NaN or negative, so
+// equal floats have the same bits.
+impl Eq for HashableToken {}
+
+impl Hash for HashableToken {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            &HashableToken::Symbol(ref s) => s.hash(state),
+            &HashableToken::Integer(i) => i.hash(state),
+            &HashableToken::Float(f) => f.to_bits().hash(state),
+            &HashableToken::Boolean(b) => b.hash(state),
+            &HashableToken::None => {}
+        }
+    }
+}
+
 impl HashableToken {
     pub fn as_token(&self) -> Token {
         match self {
             &HashableToken::Symbol(ref s) => Token::Symbol(s.clone()),
             &HashableToken::Integer(i) => Token::Integer(i),
+            &HashableToken::Float(f) => Token::Float(f),
             &HashableToken::Boolean(b) => Token::Boolean(b),
             &HashableToken::None => Token::None,
         }
     }
+
+    /// true for `_`, the key of the default arm of a match.
+    pub fn is_wildcard(&self) -> bool {
+        match self {
+            &HashableToken::Symbol(ref s) => **s == "_",
+            _ => false,
+        }
+    }
 }
 
-pub type Map = HashMap<HashableToken, Token>;
+/// maps keep their entries in the order of the source, which
+/// is the order that the arms of a match are tested in.
+pub type Map = IndexMap<HashableToken, Token>;
+
+/// A line in a source file. Tokens produced by a macro
+/// are located in the macro body, and also keep the
+/// expansion that produced them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub file: Rc<String>,
+    pub line: usize,
+    pub expansion: Option<Rc<Expansion>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expansion {
+    pub macro_name: String,
+    pub call_site: Location,
+}
+
+impl Location {
+    pub fn new(file: Rc<String>, line: usize) -> Location {
+        Location {
+            file,
+            line,
+            expansion: None,
+        }
+    }
+
+    /// describe the location, followed by every expansion
+    /// that produced it, one per line. Consecutive identical
+    /// expansions, from a recursive macro, are collapsed:
+    ///
+    /// at lib.ds:7
+    /// in expansion of `for-range` at file.ds:12
+    /// in expansion of `loop` at file.ds:3 (x4)
+    pub fn describe(&self) -> String {
+        let mut expansions: Vec<(String, usize)> = vec![];
+        let mut expansion = self.expansion.clone();
+        while let Some(e) = expansion {
+            let line = format!("in expansion of `{}` at {}", e.macro_name, e.call_site);
+            match expansions.last_mut() {
+                Some(ref mut last) if last.0 == line => last.1 += 1,
+                _ => expansions.push((line, 1)),
+            }
+            expansion = e.call_site.expansion.clone();
+        }
+        let mut lines = vec![format!("at {}", self)];
+        lines.extend(expansions.into_iter().map(|(line, count)| match count {
+            1 => line,
+            _ => format!("{} (x{})", line, count),
+        }));
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
 
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -86,6 +200,7 @@ impl fmt::Display for Token {
             &Token::Comment(ref s) => write!(f, "# {}", s),
             &Token::Symbol(ref s) => write!(f, "{}", s),
             &Token::String(ref s) => write!(f, "{}", s),
+            &Token::Float(x) => write!(f, "{:?}", x),
             &Token::Integer(i) => write!(f, "{}", i),
             &Token::Boolean(b) => write!(f, "{}", b),
             &Token::Map(ref d) => {
@@ -96,6 +211,7 @@ impl fmt::Display for Token {
                 write!(f, "}}")
             }
             &Token::None => write!(f, "None"),
+            &Token::Located(_, ref t) => write!(f, "{}", t),
         }
     }
 }
@@ -111,6 +227,7 @@ impl fmt::Display for HashableToken {
         match self {
             &HashableToken::Symbol(ref s) => write!(f, "{}", s),
             &HashableToken::Integer(i) => write!(f, "{}", i),
+            &HashableToken::Float(x) => write!(f, "{:?}", x),
             &HashableToken::Boolean(b) => write!(f, "{}", b),
             &HashableToken::None => write!(f, "None"),
         }
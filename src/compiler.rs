@@ -1,10 +1,16 @@
-use super::{get_builtin_expressions, BuiltinExpressions, FunctionType, TypeSet};
+use super::{get_builtin_expressions, BuiltinExpressions, FunctionType, Location, Type, TypeSet};
 use std::collections::{HashMap};
 
 pub struct CompilerData {
     pub functions: HashMap<String, FunctionType>,
     pub builtin_expressions: BuiltinExpressions,
     pub types: TypeSet,
+    /// the location of the token being compiled. On
+    /// an error, this is where the error occurred.
+    pub location: Option<Location>,
+    /// the types of the map literals of the
+    /// function being compiled, by their address.
+    pub map_types: HashMap<usize, Type>,
 }
 
 impl CompilerData {
@@ -13,6 +19,8 @@ impl CompilerData {
             functions: HashMap::new(),
             builtin_expressions: get_builtin_expressions(),
             types: TypeSet::new(),
+            location: None,
+            map_types: HashMap::new(),
         }
     }
 }
\ No newline at end of file
@@ -15,16 +15,12 @@ fn typecheck(
     _function: &TypevarFunction,
     args: &Vec<TypeVar>,
 ) -> GenericResult<TypeVar> {
-    // TODO: figure out how to recurse into nested
-    // data structure type variables.
-    resolver.add_constraint(Constraint::Equality(args[1].clone(), args[0].clone()))?;
-    resolver.add_constraint(Constraint::IsLiteral(
-        args[0].clone(),
-        Unresolved::Literal(TypecheckType::Int),
-    ))?;
-    Ok(args[0].clone())
+    type_classes::typecheck_class(resolver, args, type_classes::add())
 }
 
 pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
-    codegen_binop(context, args, LLVMOpcode::LLVMAdd)
+    codegen_binop(context, args, |typ| match typ {
+        Type::Float => LLVMOpcode::LLVMFAdd,
+        _ => LLVMOpcode::LLVMAdd,
+    })
 }
@@ -0,0 +1,85 @@
+/// Multiplication and division. Dividing an integer by
+/// zero, or the smallest signed integer by -1, exits the
+/// program with an error.
+use super::*;
+
+fn boostrap_compiler(compiler: &mut Compiler) {
+    add_function_to_compiler(
+        compiler,
+        "division-by-zero",
+        Type::None,
+        &[],
+        "division_by_zero",
+    );
+    add_function_to_compiler(
+        compiler,
+        "division-overflow",
+        Type::None,
+        &[],
+        "division_overflow",
+    );
+}
+
+/// multiply the arguments.
+pub fn multiply() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck,
+        codegen: |context, args| {
+            codegen_binop(context, args, |typ| match typ {
+                Type::Float => LLVMOpcode::LLVMFMul,
+                _ => LLVMOpcode::LLVMMul,
+            })
+        },
+    }
+}
+
+/// divide the first argument by the second. Integer
+/// division rounds towards zero.
+pub fn divide() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck,
+        codegen: |context, args| {
+            codegen_division(context, args, |typ| match typ {
+                Type::Float => LLVMOpcode::LLVMFDiv,
+                typ if is_unsigned(typ) => LLVMOpcode::LLVMUDiv,
+                _ => LLVMOpcode::LLVMSDiv,
+            })
+        },
+    }
+}
+
+/// the remainder of dividing the first argument by the
+/// second, which has the sign of the first.
+pub fn modulo() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck,
+        codegen: |context, args| {
+            codegen_division(context, args, |typ| match typ {
+                Type::Float => LLVMOpcode::LLVMFRem,
+                typ if is_unsigned(typ) => LLVMOpcode::LLVMURem,
+                _ => LLVMOpcode::LLVMSRem,
+            })
+        },
+    }
+}
+
+fn typecheck(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    type_classes::typecheck_class(resolver, args, type_classes::multiply())
+}
+
+#[no_mangle]
+pub extern "C" fn division_by_zero() {
+    runtime_error("division by zero");
+}
+
+#[no_mangle]
+pub extern "C" fn division_overflow() {
+    runtime_error("division overflow");
+}
@@ -0,0 +1,134 @@
+/// Bitwise operations on integers. and, or and
+/// xor also operate on booleans, where and and or
+/// only evaluate their second argument if needed.
+use super::*;
+
+fn boostrap_compiler(compiler: &mut Compiler) {
+    add_function_to_compiler(
+        compiler,
+        "shift-out-of-range",
+        Type::None,
+        &[],
+        "shift_out_of_range",
+    );
+}
+
+/// the bitwise and of the arguments. For booleans,
+/// the second is only evaluated if the first is true.
+pub fn and() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_bits,
+        codegen: |context, args| codegen_logical(context, args, LLVMOpcode::LLVMAnd, true),
+    }
+}
+
+/// the bitwise or of the arguments. For booleans,
+/// the second is only evaluated if the first is false.
+pub fn or() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_bits,
+        codegen: |context, args| codegen_logical(context, args, LLVMOpcode::LLVMOr, false),
+    }
+}
+
+/// the bitwise exclusive or of the arguments.
+pub fn xor() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_bits,
+        codegen: |context, args| codegen_binop(context, args, |_| LLVMOpcode::LLVMXor),
+    }
+}
+
+/// shift the first argument left by the second.
+pub fn shift_left() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_shift,
+        codegen: |context, args| codegen_shift(context, args, |_| LLVMOpcode::LLVMShl),
+    }
+}
+
+/// shift the first argument right by the second. Signed
+/// integers keep their sign.
+pub fn shift_right() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_shift,
+        codegen: |context, args| {
+            codegen_shift(context, args, |typ| {
+                if is_unsigned(typ) {
+                    LLVMOpcode::LLVMLShr
+                } else {
+                    LLVMOpcode::LLVMAShr
+                }
+            })
+        },
+    }
+}
+
+/// generate an and or an or. If the first argument is a boolean,
+/// the second is evaluated only if the first is equal to
+/// evaluate_when, otherwise the result is the first argument.
+fn codegen_logical(
+    context: &mut Context,
+    args: &[Token],
+    opcode: LLVMOpcode,
+    evaluate_when: bool,
+) -> CodegenResult<Object> {
+    if args.len() != 2 {
+        return Err(CodegenError::new(&format!(
+            "binary expression should only have two arguments. found {}",
+            args.len()
+        )));
+    };
+    let lhs = gen_token(context, &args[0])?;
+    if lhs.object_type != Type::Bool {
+        let rhs = gen_token(context, &args[1])?;
+        return Ok(build_binop(context, opcode, &lhs, &rhs));
+    }
+    // the result is the first argument if the second is
+    // not evaluated, and the second argument otherwise.
+    let rhs_block = context.create_block("evaluate_rhs".to_owned());
+    let skip_block = context.create_block("skip_rhs".to_owned());
+    let (true_block, false_block) = if evaluate_when {
+        (rhs_block, skip_block)
+    } else {
+        (skip_block, rhs_block)
+    };
+    context.add_instruction(LLVMInstruction::BuildCondBr {
+        value: lhs.index,
+        true_block: true_block,
+        false_block: false_block,
+    });
+    let mut values = vec![(skip_block, lhs)];
+    context.block = rhs_block;
+    let rhs = gen_token(context, &args[1])?;
+    if !context.current_block().has_been_terminated() {
+        values.push((context.block, rhs));
+    }
+    merge_branches(context, "after_rhs", values, true)
+}
+
+fn typecheck_bits(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    type_classes::typecheck_class(resolver, args, type_classes::bits())
+}
+
+fn typecheck_shift(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    type_classes::typecheck_class(resolver, args, type_classes::shift())
+}
+
+#[no_mangle]
+pub extern "C" fn shift_out_of_range() {
+    runtime_error("shift amount out of range");
+}
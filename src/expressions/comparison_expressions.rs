@@ -0,0 +1,82 @@
+/// Comparisons of two values of the same type,
+/// which all return a Bool.
+use super::*;
+use llvm_sys::LLVMIntPredicate::*;
+use llvm_sys::LLVMRealPredicate::*;
+
+fn boostrap_compiler(_compiler: &mut Compiler) {}
+
+/// true if the first argument is less than the second.
+pub fn less_than() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_ord,
+        codegen: |context, args| {
+            codegen_comparison(context, args, LLVMIntSLT, LLVMIntULT, LLVMRealOLT)
+        },
+    }
+}
+
+/// true if the first argument is less than or equal to the second.
+pub fn less_or_equal() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_ord,
+        codegen: |context, args| {
+            codegen_comparison(context, args, LLVMIntSLE, LLVMIntULE, LLVMRealOLE)
+        },
+    }
+}
+
+/// true if the first argument is greater than the second.
+pub fn greater_than() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_ord,
+        codegen: |context, args| {
+            codegen_comparison(context, args, LLVMIntSGT, LLVMIntUGT, LLVMRealOGT)
+        },
+    }
+}
+
+/// true if the first argument is greater than or equal to the second.
+pub fn greater_or_equal() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_ord,
+        codegen: |context, args| {
+            codegen_comparison(context, args, LLVMIntSGE, LLVMIntUGE, LLVMRealOGE)
+        },
+    }
+}
+
+/// true if the arguments are not equal.
+pub fn not_equal() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _function, args| {
+            typecheck_comparison(resolver, args, type_classes::eq())
+        },
+        // NaN is not equal to anything, itself included.
+        codegen: |context, args| {
+            codegen_comparison(context, args, LLVMIntNE, LLVMIntNE, LLVMRealUNE)
+        },
+    }
+}
+
+fn typecheck_ord(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    typecheck_comparison(resolver, args, type_classes::ord())
+}
+
+fn typecheck_comparison(
+    resolver: &mut TypeResolver<TypecheckType>,
+    args: &Vec<TypeVar>,
+    class: TypeClass<TypecheckType>,
+) -> GenericResult<TypeVar> {
+    type_classes::typecheck_class(resolver, args, class)?;
+    typecheck_literals(resolver, &vec![], &[], TypecheckType::Bool)
+}
@@ -0,0 +1,106 @@
+/// Conditionals, which evaluate the value of the first
+/// branch whose condition is true. `if` is a `cond` with
+/// a single condition, and an optional else value:
+///
+/// if cond then else
+/// cond cond1 value1 cond2 value2 else
+use super::*;
+
+fn boostrap_compiler(_compiler: &mut Compiler) {}
+
+pub fn if_else() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, function, args| {
+            if args.len() != 2 && args.len() != 3 {
+                return Err(Box::new(DispError::new(&format!(
+                    "expected a condition, a value and an optional else value, found {} arguments",
+                    args.len()
+                ))));
+            }
+            typecheck_cond(resolver, function, args)
+        },
+        codegen: |context, args| {
+            if args.len() != 2 && args.len() != 3 {
+                return Err(CodegenError::new(&format!(
+                    "if expression should have two or three arguments. found {}",
+                    args.len()
+                )));
+            }
+            codegen_cond(context, args)
+        },
+    }
+}
+
+pub fn cond() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_cond,
+        codegen: codegen_cond,
+    }
+}
+
+/// the conditions are Bool. Without an else value, the
+/// values of the branches are discarded.
+fn typecheck_cond(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    if args.len() < 2 {
+        return Err(Box::new(DispError::new(&format!(
+            "expected a condition and a value, found {} arguments",
+            args.len()
+        ))));
+    }
+    for branch in args.chunks(2) {
+        if branch.len() == 2 {
+            resolver.add_constraint(Constraint::IsLiteral(
+                branch[0],
+                Unresolved::Literal(TypecheckType::Bool),
+            ))?;
+        }
+    }
+    if args.len() % 2 == 0 {
+        return typecheck_literals(resolver, &vec![], &[], TypecheckType::None);
+    }
+    let result = args[args.len() - 1];
+    for branch in args.chunks(2) {
+        if branch.len() == 2 {
+            resolver.add_constraint(Constraint::Equality(result, branch[1]))?;
+        }
+    }
+    Ok(result)
+}
+
+fn codegen_cond(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
+    let has_else = args.len() % 2 == 1;
+    let mut values = vec![];
+    for branch in args.chunks(2) {
+        if branch.len() == 1 {
+            let value = gen_token(context, &branch[0])?;
+            if !context.current_block().has_been_terminated() {
+                values.push((context.block, value));
+            }
+            break;
+        }
+        let condition = gen_token(context, &branch[0])?;
+        let then_block = context.create_block("then".to_owned());
+        let else_block = context.create_block("else".to_owned());
+        context.add_instruction(LLVMInstruction::BuildCondBr {
+            value: condition.index,
+            true_block: then_block,
+            false_block: else_block,
+        });
+        context.block = then_block;
+        let value = gen_token(context, &branch[1])?;
+        if !context.current_block().has_been_terminated() {
+            values.push((context.block, value));
+        }
+        context.block = else_block;
+    }
+    if !has_else {
+        values.push((context.block, Object::none()));
+    }
+    merge_branches(context, "after_cond", values, has_else)
+}
@@ -15,28 +15,16 @@ fn typecheck(
     _function: &TypevarFunction,
     args: &Vec<TypeVar>,
 ) -> GenericResult<TypeVar> {
-    let type_var = resolver.create_type_var();
-    resolver.add_constraint(Constraint::Equality(args[0], args[1]))?;
-    resolver.add_constraint(Constraint::IsLiteral(type_var, 
-        Unresolved::Literal(TypecheckType::Bool)
-    ))?;
-    Ok(type_var)
+    type_classes::typecheck_class(resolver, args, type_classes::eq())?;
+    typecheck_literals(resolver, &vec![], &[], TypecheckType::Bool)
 }
 
 pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
-    if args.len() != 2 {
-        return Err(CodegenError::new(&format!(
-            "eq expression should only have two arguments. found {}",
-            args.len()
-        )));
-    };
-    let lhs = gen_token(context, &args[0])?;
-    let rhs = gen_token(context, &args[1])?;
-    let result = context.allocate(Type::Bool);
-    context.add_instruction(LLVMInstruction::BuildICmp {
-        lhs: lhs.index,
-        rhs: rhs.index,
-        target: result.index,
-    });
-    Ok(result)
+    codegen_comparison(
+        context,
+        args,
+        LLVMIntPredicate::LLVMIntEQ,
+        LLVMIntPredicate::LLVMIntEQ,
+        LLVMRealPredicate::LLVMRealOEQ,
+    )
 }
@@ -0,0 +1,36 @@
+use super::*;
+use std::ffi::CStr;
+
+pub fn expression() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck,
+        codegen: codegen,
+    }
+}
+
+fn boostrap_compiler(compiler: &mut Compiler) {
+    add_function_to_compiler(compiler, "float", Type::Float, &vec![Type::String], "float");
+}
+
+fn typecheck(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    type_classes::typecheck_conversion(resolver, args, type_classes::to_float())?;
+    typecheck_literals(resolver, &vec![], &[], TypecheckType::Float)
+}
+
+pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
+    codegen_conversion(context, args, Type::Float, "float")
+}
+
+#[no_mangle]
+pub extern "C" fn float(value: *const c_char) -> f64 {
+    let s = unsafe { CStr::from_ptr(value).to_str().unwrap() };
+    match s.parse::<f64>() {
+        Ok(f) => f,
+        Err(_) => runtime_error(&format!("unable to convert \"{}\" to Float", s)),
+    }
+}
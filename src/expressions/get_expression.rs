@@ -1,5 +1,4 @@
 use super::*;
-use llvm_sys::core::*;
 
 pub fn expression() -> Expression {
     Expression {
@@ -42,12 +41,6 @@ pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
         target: array_pointer_gep,
     });
     let array_pointer = context.allocate_without_type();
-    let byte_pointer_type =
-        unsafe { LLVMPointerType(LLVMInt8TypeInContext(context.compiler.llvm.context), 0) };
-    context.add_instruction(LLVMInstruction::BuildAlloca {
-        llvm_type: byte_pointer_type,
-        target: array_pointer,
-    });
     context.add_instruction(LLVMInstruction::BuildLoad {
         source: array_pointer_gep,
         target: array_pointer,
@@ -69,12 +62,7 @@ pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
             )));
         }
     };
-    let llvm_byte_type = context.compiler.llvm.types.get(&result_type);
     let result = context.allocate(result_type);
-    context.add_instruction(LLVMInstruction::BuildAlloca {
-        llvm_type: llvm_byte_type,
-        target: result.index,
-    });
     context.add_instruction(LLVMInstruction::BuildLoad {
         source: value_pointer,
         target: result.index,
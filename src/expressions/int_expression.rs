@@ -11,6 +11,13 @@ pub fn expression() -> Expression {
 
 fn boostrap_compiler(compiler: &mut Compiler) {
     add_function_to_compiler(compiler, "int", Type::Int, &vec![Type::String], "int");
+    add_function_to_compiler(
+        compiler,
+        "int-out-of-range",
+        Type::None,
+        &[Type::Float],
+        "int_out_of_range",
+    );
 }
 
 fn typecheck(
@@ -18,24 +25,24 @@ fn typecheck(
     _function: &TypevarFunction,
     args: &Vec<TypeVar>,
 ) -> GenericResult<TypeVar> {
-    let type_var = resolver.create_type_var();
-    resolver.add_constraint(Constraint::IsLiteral(
-        args[0],
-        Unresolved::Literal(TypecheckType::String),
-    ))?;
-    resolver.add_constraint(Constraint::IsLiteral(
-        type_var,
-        Unresolved::Literal(TypecheckType::Int),
-    ))?;
-    Ok(type_var)
+    type_classes::typecheck_conversion(resolver, args, type_classes::to_int())?;
+    typecheck_literals(resolver, &vec![], &[], TypecheckType::Int)
 }
 
 pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
-    call_function(context, "int", args)
+    codegen_conversion(context, args, Type::Int, "int")
 }
 
 #[no_mangle]
 pub extern "C" fn int(value: *const c_char) -> i64 {
     let s = unsafe { CStr::from_ptr(value).to_str().unwrap() };
-    s.parse::<i64>().unwrap()
+    match s.parse::<i64>() {
+        Ok(i) => i,
+        Err(_) => runtime_error(&format!("unable to convert \"{}\" to Int", s)),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn int_out_of_range(value: f64) {
+    runtime_error(&format!("unable to convert {} to Int: out of range", value));
 }
@@ -0,0 +1,151 @@
+/// Integers of a fixed size, and the builtins that convert to
+/// them. Conversions are checked: converting a value that does
+/// not fit in the type is an error.
+use super::*;
+use std::convert::TryFrom;
+use std::fmt::Display;
+
+/// the integers of a fixed size, along with
+/// the name of the builtin that converts to them.
+fn sized_integers() -> Vec<(Type, &'static str)> {
+    vec![
+        (Type::I8, "i8"),
+        (Type::I16, "i16"),
+        (Type::I32, "i32"),
+        (Type::U8, "u8"),
+        (Type::U64, "u64"),
+    ]
+}
+
+fn boostrap_compiler(compiler: &mut Compiler) {
+    // integers are extended to an Int or a U64
+    // before they are converted.
+    for (typ, name) in sized_integers() {
+        for (source, source_name) in vec![(Type::Int, "int"), (Type::U64, "u64")] {
+            if source != typ {
+                add_function_to_compiler(
+                    compiler,
+                    name,
+                    typ.clone(),
+                    &[source],
+                    &format!("{}_from_{}", name, source_name),
+                );
+            }
+        }
+        add_function_to_compiler(
+            compiler,
+            "print",
+            Type::None,
+            &[typ.clone()],
+            &format!("print_{}", name),
+        );
+    }
+    add_function_to_compiler(compiler, "int", Type::Int, &[Type::U64], "int_from_u64");
+}
+
+/// convert an integer to an I8.
+pub fn i8() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _function, args| typecheck(resolver, args, TypecheckType::I8),
+        codegen: |context, args| codegen_conversion(context, args, Type::I8, "i8"),
+    }
+}
+
+/// convert an integer to an I16.
+pub fn i16() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _function, args| typecheck(resolver, args, TypecheckType::I16),
+        codegen: |context, args| codegen_conversion(context, args, Type::I16, "i16"),
+    }
+}
+
+/// convert an integer to an I32.
+pub fn i32() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _function, args| typecheck(resolver, args, TypecheckType::I32),
+        codegen: |context, args| codegen_conversion(context, args, Type::I32, "i32"),
+    }
+}
+
+/// convert an integer to a U8.
+pub fn u8() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _function, args| typecheck(resolver, args, TypecheckType::U8),
+        codegen: |context, args| codegen_conversion(context, args, Type::U8, "u8"),
+    }
+}
+
+/// convert an integer to a U64.
+pub fn u64() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _function, args| typecheck(resolver, args, TypecheckType::U64),
+        codegen: |context, args| codegen_conversion(context, args, Type::U64, "u64"),
+    }
+}
+
+fn typecheck(
+    resolver: &mut TypeResolver<TypecheckType>,
+    args: &Vec<TypeVar>,
+    typ: TypecheckType,
+) -> GenericResult<TypeVar> {
+    type_classes::typecheck_conversion(resolver, args, type_classes::to_sized(&typ))?;
+    typecheck_literals(resolver, &vec![], &[], typ)
+}
+
+/// convert the value, exiting if it does not fit in the type.
+fn checked<S: Copy + Display, T: TryFrom<S>>(value: S, type_name: &str) -> T {
+    match T::try_from(value) {
+        Ok(result) => result,
+        Err(_) => runtime_error(&format!(
+            "unable to convert {} to {}: out of range",
+            value, type_name
+        )),
+    }
+}
+
+/// declare the native functions to convert
+/// an integer to a type, checking its range.
+macro_rules! checked_conversions {
+    ($($name:ident($source:ty) -> $target:ty, $type_name:expr;)*) => {$(
+        #[no_mangle]
+        pub extern "C" fn $name(value: $source) -> $target {
+            checked(value, $type_name)
+        }
+    )*};
+}
+
+checked_conversions! {
+    i8_from_int(i64) -> i8, "I8";
+    i8_from_u64(u64) -> i8, "I8";
+    i16_from_int(i64) -> i16, "I16";
+    i16_from_u64(u64) -> i16, "I16";
+    i32_from_int(i64) -> i32, "I32";
+    i32_from_u64(u64) -> i32, "I32";
+    u8_from_int(i64) -> u8, "U8";
+    u8_from_u64(u64) -> u8, "U8";
+    u64_from_int(i64) -> u64, "U64";
+    int_from_u64(u64) -> i64, "Int";
+}
+
+/// declare the native functions to print an integer.
+macro_rules! print_integers {
+    ($($name:ident($typ:ty);)*) => {$(
+        #[no_mangle]
+        pub extern "C" fn $name(value: $typ) {
+            print!("{}", value);
+        }
+    )*};
+}
+
+print_integers! {
+    print_i8(i8);
+    print_i16(i16);
+    print_i32(i32);
+    print_u8(u8);
+    print_u64(u64);
+}
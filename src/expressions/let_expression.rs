@@ -40,22 +40,15 @@ pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
         }
     };
     let target = gen_token(context, &args[1])?;
-    let result_object = context
-        .scope
-        .locals
-        .entry(*var_name.clone())
-        .or_insert({
-            let object = context.function.objects;
-            context.function.objects += 1;
-            context.function.basic_blocks[context.block].add_instruction(
-                LLVMInstruction::BuildAlloca {
-                    llvm_type: context.compiler.llvm.types.get(&target.object_type),
-                    target: object,
-                },
-            );
-            Object::new(object, target.object_type.clone())
-        })
-        .clone();
+    // a variable that is declared again keeps its memory.
+    let result_object = match context.scope.locals.get(&*var_name).cloned() {
+        Some(object) => object,
+        None => {
+            let object = context.allocate_variable(target.object_type.clone());
+            context.scope.locals.insert(*var_name, object.clone());
+            object
+        }
+    };
     context.add_instruction(LLVMInstruction::BuildStore {
         source: target.index,
         target: result_object.index,
@@ -0,0 +1,185 @@
+/// Maps, and the builtins to modify them. At runtime a map is
+/// an opaque pointer to a native map, which stores its keys and
+/// values along with their type. The same native map is used for
+/// maps of any type.
+use super::*;
+use std::fmt;
+
+/// the types that can be stored in a map, along with
+/// the name of the native functions that store them.
+fn native_types() -> Vec<(Type, &'static str)> {
+    vec![
+        (Type::Bool, "bool"),
+        (Type::Int, "int"),
+        (Type::String, "string"),
+    ]
+}
+
+fn boostrap_compiler(compiler: &mut Compiler) {
+    // every map is created by the same native function,
+    // so its declared type does not matter.
+    let any_map = Type::Map(Box::new(Type::None), Box::new(Type::None));
+    add_function_to_compiler(compiler, "create-map", any_map, &[], "create_map");
+    let mut map_types = vec![];
+    for (key, key_name) in native_types() {
+        for (value, value_name) in native_types() {
+            let map = Type::Map(Box::new(key.clone()), Box::new(value.clone()));
+            add_function_to_compiler(
+                compiler,
+                "insert",
+                Type::None,
+                &[map.clone(), key.clone(), value.clone()],
+                &format!("map_insert_{}_{}", key_name, value_name),
+            );
+            map_types.push(map);
+        }
+    }
+    for map in map_types {
+        add_function_to_compiler(compiler, "count", Type::Int, &[map.clone()], "map_count");
+        add_function_to_compiler(compiler, "print", Type::None, &[map], "print_map");
+    }
+}
+
+/// set the value of a key of the map.
+pub fn insert() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_insert,
+        codegen: |context, args| call_function(context, "insert", args),
+    }
+}
+
+/// the number of keys in the map.
+pub fn count() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: typecheck_count,
+        codegen: |context, args| call_function(context, "count", args),
+    }
+}
+
+fn typecheck_insert(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    if args.len() != 3 {
+        return Err(Box::new(DispError::new(&format!(
+            "expected 3 arguments, found {}",
+            args.len()
+        ))));
+    }
+    resolver.add_constraint(Constraint::IsLiteral(
+        args[0],
+        Unresolved::Generic(TypecheckType::Map, vec![args[1], args[2]]),
+    ))?;
+    resolver.add_constraint(Constraint::HasClass(args[1], type_classes::map_entry()))?;
+    resolver.add_constraint(Constraint::HasClass(args[2], type_classes::map_entry()))?;
+    typecheck_literals(resolver, &vec![], &[], TypecheckType::None)
+}
+
+fn typecheck_count(
+    resolver: &mut TypeResolver<TypecheckType>,
+    _function: &TypevarFunction,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    if args.len() != 1 {
+        return Err(Box::new(DispError::new(&format!(
+            "expected 1 argument, found {}",
+            args.len()
+        ))));
+    }
+    let key = resolver.create_type_var();
+    let value = resolver.create_type_var();
+    resolver.add_constraint(Constraint::IsLiteral(
+        args[0],
+        Unresolved::Generic(TypecheckType::Map, vec![key, value]),
+    ))?;
+    typecheck_literals(resolver, &vec![], &[], TypecheckType::Int)
+}
+
+/// A key or a value of a native map.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum MapValue {
+    Bool(bool),
+    Int(i64),
+    String(String),
+}
+
+impl fmt::Display for MapValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapValue::Bool(b) => write!(f, "{}", b),
+            MapValue::Int(i) => write!(f, "{}", i),
+            MapValue::String(ref s) => write!(f, "{}", s),
+        }
+    }
+}
+
+pub type NativeMap = HashMap<MapValue, MapValue>;
+
+#[no_mangle]
+pub extern "C" fn create_map() -> *mut NativeMap {
+    Box::into_raw(Box::new(NativeMap::new()))
+}
+
+#[no_mangle]
+pub extern "C" fn map_count(map: *mut NativeMap) -> i64 {
+    unsafe { (*map).len() as i64 }
+}
+
+#[no_mangle]
+pub extern "C" fn print_map(map: *mut NativeMap) {
+    let map = unsafe { &*map };
+    print!("{{");
+    for (k, v) in map {
+        print!("{}: {}, ", k, v);
+    }
+    print!("}}");
+}
+
+/// convert a native argument into a key or a value.
+trait ToMapValue {
+    fn to_map_value(self) -> MapValue;
+}
+
+impl ToMapValue for bool {
+    fn to_map_value(self) -> MapValue {
+        MapValue::Bool(self)
+    }
+}
+
+impl ToMapValue for i64 {
+    fn to_map_value(self) -> MapValue {
+        MapValue::Int(self)
+    }
+}
+
+impl ToMapValue for *const c_char {
+    fn to_map_value(self) -> MapValue {
+        MapValue::String(unsafe { CStr::from_ptr(self).to_str().unwrap().to_owned() })
+    }
+}
+
+/// declare the native functions to insert a key
+/// and a value of the types into a map.
+macro_rules! map_insert {
+    ($($name:ident($key:ty, $value:ty);)*) => {$(
+        #[no_mangle]
+        pub extern "C" fn $name(map: *mut NativeMap, key: $key, value: $value) {
+            unsafe { (*map).insert(key.to_map_value(), value.to_map_value()) };
+        }
+    )*};
+}
+
+map_insert! {
+    map_insert_bool_bool(bool, bool);
+    map_insert_bool_int(bool, i64);
+    map_insert_bool_string(bool, *const c_char);
+    map_insert_int_bool(i64, bool);
+    map_insert_int_int(i64, i64);
+    map_insert_int_string(i64, *const c_char);
+    map_insert_string_bool(*const c_char, bool);
+    map_insert_string_int(*const c_char, i64);
+    map_insert_string_string(*const c_char, *const c_char);
+}
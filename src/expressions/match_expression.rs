@@ -10,14 +10,34 @@ pub fn expression() -> Expression {
 
 fn boostrap_compiler(_compiler: &mut Compiler) {}
 
+/// the arms are a map from the values matched to the value of the
+/// arm. An exhaustive match passes the type of its value as a
+/// third argument. Like a cond without an else value, any other
+/// match does not produce a value.
 fn typecheck(
-    _resolver: &mut TypeResolver<TypecheckType>,
+    resolver: &mut TypeResolver<TypecheckType>,
     _: &TypevarFunction,
     args: &Vec<TypeVar>,
 ) -> GenericResult<TypeVar> {
-    // TODO: figure out how to recurse into nested
-    // data structure type variables.
-    Ok(args[0].clone())
+    if args.len() != 2 && args.len() != 3 {
+        return Err(Box::new(DispError::new(&format!(
+            "expected a value to match and a map of arms, found {} arguments",
+            args.len()
+        ))));
+    }
+    let value = resolver.create_type_var();
+    resolver.add_constraint(Constraint::HasClass(args[0], type_classes::eq()))?;
+    resolver.add_constraint(Constraint::IsLiteral(
+        args[1],
+        Unresolved::Generic(TypecheckType::Map, vec![args[0], value]),
+    ))?;
+    match args.get(2) {
+        Some(result) => {
+            resolver.add_constraint(Constraint::Equality(value, *result))?;
+            Ok(value)
+        }
+        None => typecheck_literals(resolver, &vec![], &[], TypecheckType::None),
+    }
 }
 
 pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
@@ -27,57 +47,125 @@ pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
             args
         )));
     };
+    let arms = match &args[1] {
+        Token::Map(ref map) => map,
+        _ => {
+            return Err(CodegenError::new(&format!(
+                "match expression should be map. found {}",
+                &args[1]
+            )));
+        }
+    };
     let condition = gen_token(context, &args[0])?;
-    let post_switch_block = context.create_block("postswitch".to_owned());
-    if let Token::Map(ref map) = &args[1] {
-        let mut key_values = vec![];
-        // we construct all keys first, to ensure
-        // that they exist before the match statement is
-        // executed.
-        // TODO: reject keys that are not constants
-        for key in map.keys() {
-            let key_value = gen_token(context, &key.as_token())?;
-            key_values.push(key_value);
+    let match_block = context.block;
+    // only an exhaustive match produces a value, which was
+    // checked during type annotation.
+    let exhaustive = arms.keys().any(|key| key.is_wildcard())
+        || (arms.contains_key(&HashableToken::Boolean(true))
+            && arms.contains_key(&HashableToken::Boolean(false)));
+    let mut cases = vec![];
+    let mut default_block = None;
+    let mut values = vec![];
+    for (key, value) in arms.iter() {
+        let block = context.create_block("case".to_owned());
+        context.block = block;
+        let value = gen_token(context, value)?;
+        if !context.current_block().has_been_terminated() {
+            values.push((context.block, value));
         }
-        let num_cases = (map.len() - 1) as u32;
-        let switch = context.allocate_without_type();
-        context.add_instruction(LLVMInstruction::BuildSwitch {
-            value: condition.index,
-            post_switch_block,
-            num_cases,
-            target: switch,
-        });
-        for (index, (_key, value)) in map.iter().enumerate() {
-            let block = context.create_block("case".to_owned());
-            let branch_key = &key_values[index];
-            context.add_instruction(LLVMInstruction::AddCase {
-                switch,
-                value: branch_key.index,
-                block: block,
-            });
-            let mut branch_context = Context::new(
-                context.function_map,
-                context.compiler,
-                context.function,
-                context.scope,
-                block,
-            );
-            // context.add_instruction(LLVMInstruction::PositionBuilderAtEnd { block });
-            // TODO: capture this value and make it the return value of the
-            // match statement.
-            gen_token(&mut branch_context, value)?;
-            if !branch_context.current_block().has_been_terminated() {
-                branch_context.add_instruction(LLVMInstruction::BuildBr {
-                    block: post_switch_block,
-                });
-            }
+        if key.is_wildcard() {
+            default_block = Some(block);
+        } else {
+            cases.push((key, block));
         }
+    }
+    let default_block = match default_block {
+        Some(block) => block,
+        // without a default arm, a match on both true
+        // and false takes its last arm by default.
+        None if exhaustive => cases.pop().unwrap().1,
+        // a value that matches no arm skips the match.
+        None => {
+            let block = context.create_block("nomatch".to_owned());
+            values.push((block, Object::none()));
+            block
+        }
+    };
+    context.block = match_block;
+    if cases.iter().all(|(key, _)| is_constant(key)) {
+        build_switch(context, &condition, &cases, default_block)?;
     } else {
-        return Err(CodegenError::new(&format!(
-            "match expression should be map. found {}",
-            &args[1]
-        )));
+        build_comparisons(context, &condition, &cases, default_block)?;
+    }
+    merge_branches(context, "postmatch", values, exhaustive)
+}
+
+/// only constants can be the cases of a switch.
+fn is_constant(key: &HashableToken) -> bool {
+    match key {
+        HashableToken::Integer(_) | HashableToken::Boolean(_) => true,
+        _ => false,
+    }
+}
+
+fn build_switch(
+    context: &mut Context,
+    condition: &Object,
+    cases: &[(&HashableToken, usize)],
+    default_block: usize,
+) -> CodegenResult<()> {
+    // we construct all keys first, to ensure
+    // that they exist before the match statement is
+    // executed.
+    let mut key_values = vec![];
+    for (key, _) in cases {
+        key_values.push(gen_token(context, &key.as_token())?);
+    }
+    let switch = context.allocate_without_type();
+    context.add_instruction(LLVMInstruction::BuildSwitch {
+        value: condition.index,
+        post_switch_block: default_block,
+        num_cases: cases.len() as u32,
+        target: switch,
+    });
+    for ((_, block), key_value) in cases.iter().zip(key_values) {
+        context.add_instruction(LLVMInstruction::AddCase {
+            switch,
+            value: key_value.index,
+            block: *block,
+        });
+    }
+    Ok(())
+}
+
+/// compare the condition to each key in turn, as keys
+/// that are not constants can not be the cases of a switch.
+fn build_comparisons(
+    context: &mut Context,
+    condition: &Object,
+    cases: &[(&HashableToken, usize)],
+    default_block: usize,
+) -> CodegenResult<()> {
+    for (key, block) in cases {
+        let key_value = gen_token(context, &key.as_token())?;
+        let is_equal = build_comparison(
+            context,
+            condition,
+            &key_value,
+            LLVMIntPredicate::LLVMIntEQ,
+            LLVMIntPredicate::LLVMIntEQ,
+            LLVMRealPredicate::LLVMRealOEQ,
+        );
+        let next_block = context.create_block("next_case".to_owned());
+        context.add_instruction(LLVMInstruction::BuildCondBr {
+            value: is_equal.index,
+            true_block: *block,
+            false_block: next_block,
+        });
+        context.block = next_block;
     }
-    context.block = post_switch_block;
-    Ok(Object::none())
+    context.add_instruction(LLVMInstruction::BuildBr {
+        block: default_block,
+    });
+    Ok(())
 }
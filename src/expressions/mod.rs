@@ -1,22 +1,33 @@
 use self::utils::codegen_binop;
-use super::llvm_codegen::{compiler::gen_token, CodegenError, CodegenResult, Compiler, Scope};
+use super::llvm_codegen::{
+    compiler::{function_symbol, gen_token},
+    copy_returned_array, CodegenError, CodegenResult, Compiler, Scope,
+};
 use super::{
-    Array, CompilerData, Context, FunctionType, GenericResult, LLVMInstruction, NativeFunction,
-    Object, Token, Type, TypecheckType, TypevarFunction, AnnotatorScope
+    Array, CompilerData, Context, DispError, FunctionType, GenericResult, HashableToken,
+    LLVMInstruction, NativeFunction, Object, Token, Type, TypecheckType, TypevarFunction,
+    AnnotatorScope, own_token
 };
-use inference::{Constraint, TypeResolver, TypeVar, Unresolved};
+use inference::{Constraint, TypeClass, TypeResolver, TypeVar, Unresolved};
 use libc::c_char;
 use llvm_sys::*;
 use std::{collections::HashMap, ffi::CStr};
 
 mod add_expression;
+mod arithmetic_expressions;
+mod bitwise_expressions;
 mod bytes_builtins;
 pub use self::bytes_builtins::*;
+mod comparison_expressions;
+mod conditional_expressions;
 mod eq_expression;
+mod float_expression;
 mod get_expression;
 mod int_expression;
+mod integer_expressions;
 mod len_expression;
 mod let_expression;
+mod map_expressions;
 mod match_expression;
 mod not_expression;
 mod print_expression;
@@ -28,7 +39,13 @@ pub use self::print_expression::*;
 pub use self::readline_expression::*;
 mod return_expression;
 mod subtract_expression;
+mod token_builtins;
+pub use self::token_builtins::*;
+mod token_expressions;
+mod type_classes;
+pub use self::type_classes::{map_entry, partial_match};
 mod utils;
+pub use self::utils::runtime_error;
 use self::utils::*;
 /// This module contains all the expressions that are
 /// built in.
@@ -50,16 +67,57 @@ pub fn get_builtin_expressions() -> BuiltinExpressions {
     let mut expressions = HashMap::new();
     expressions.insert(String::from("-"), subtract_expression::expression());
     expressions.insert(String::from("+"), add_expression::expression());
+    expressions.insert(String::from("*"), arithmetic_expressions::multiply());
+    expressions.insert(String::from("/"), arithmetic_expressions::divide());
+    expressions.insert(String::from("<"), comparison_expressions::less_than());
+    expressions.insert(String::from("<="), comparison_expressions::less_or_equal());
+    expressions.insert(String::from(">"), comparison_expressions::greater_than());
+    expressions.insert(String::from(">="), comparison_expressions::greater_or_equal());
+    expressions.insert(String::from("and"), bitwise_expressions::and());
+    expressions.insert(String::from("cond"), conditional_expressions::cond());
+    expressions.insert(String::from("count"), map_expressions::count());
     expressions.insert(String::from("eq"), eq_expression::expression());
+    expressions.insert(String::from("float"), float_expression::expression());
     expressions.insert(String::from("get"), get_expression::expression());
+    expressions.insert(String::from("i8"), integer_expressions::i8());
+    expressions.insert(String::from("if"), conditional_expressions::if_else());
+    expressions.insert(String::from("i16"), integer_expressions::i16());
+    expressions.insert(String::from("i32"), integer_expressions::i32());
+    expressions.insert(String::from("insert"), map_expressions::insert());
     expressions.insert(String::from("int"), int_expression::expression());
     expressions.insert(String::from("let"), let_expression::expression());
     expressions.insert(String::from("len"), len_expression::expression());
     expressions.insert(String::from("match"), match_expression::expression());
+    expressions.insert(String::from("mod"), arithmetic_expressions::modulo());
+    expressions.insert(String::from("neq"), comparison_expressions::not_equal());
     expressions.insert(String::from("not"), not_expression::expression());
+    expressions.insert(String::from("or"), bitwise_expressions::or());
     expressions.insert(String::from("print"), print_expression::expression());
     expressions.insert(String::from("return"), return_expression::expression());
     expressions.insert(String::from("read-line"), readline_expression::expression());
+    expressions.insert(String::from("shl"), bitwise_expressions::shift_left());
+    expressions.insert(String::from("shr"), bitwise_expressions::shift_right());
+    expressions.insert(String::from("u8"), integer_expressions::u8());
+    expressions.insert(String::from("u64"), integer_expressions::u64());
     expressions.insert(String::from("while"), while_expression::expression());
+    expressions.insert(String::from("xor"), bitwise_expressions::xor());
+    // the token api, used by procedural macros.
+    expressions.insert(String::from("function-arity"), token_expressions::function_arity());
+    expressions.insert(String::from("function-exists"), token_expressions::function_exists());
+    expressions.insert(
+        String::from("function-signature"),
+        token_expressions::function_signature(),
+    );
+    expressions.insert(String::from("gensym"), token_expressions::gensym());
+    expressions.insert(String::from("macro-argument"), token_expressions::macro_argument());
+    expressions.insert(String::from("macro-result"), token_expressions::macro_result());
+    expressions.insert(String::from("make-symbol"), token_expressions::make_symbol());
+    expressions.insert(String::from("make-token"), token_expressions::make_token());
+    expressions.insert(String::from("symbol-name"), token_expressions::symbol_name());
+    expressions.insert(String::from("token-get"), token_expressions::token_get());
+    expressions.insert(String::from("token-is"), token_expressions::token_is());
+    expressions.insert(String::from("token-len"), token_expressions::token_len());
+    expressions.insert(String::from("token-push"), token_expressions::token_push());
+    expressions.insert(String::from("type-of"), token_expressions::type_of());
     expressions
 }
@@ -10,6 +10,13 @@ pub fn expression() -> Expression {
 
 fn boostrap_compiler(compiler: &mut Compiler) {
     add_function_to_compiler(compiler, "print", Type::None, &vec![Type::Int], "print_int");
+    add_function_to_compiler(
+        compiler,
+        "print",
+        Type::None,
+        &vec![Type::Float],
+        "print_float",
+    );
     add_function_to_compiler(
         compiler,
         "print",
@@ -31,13 +38,6 @@ fn boostrap_compiler(compiler: &mut Compiler) {
         &vec![Type::Array(Box::new(Type::Byte))],
         "print_bytes",
     );
-    add_function_to_compiler(
-        compiler,
-        "print",
-        Type::None,
-        &vec![Type::Map(Box::new(Type::String), Box::new(Type::Int))],
-        "print_map",
-    );
     add_function_to_compiler(
         compiler,
         "print",
@@ -50,31 +50,18 @@ fn boostrap_compiler(compiler: &mut Compiler) {
 fn typecheck(
     resolver: &mut TypeResolver<TypecheckType>,
     _function: &TypevarFunction,
-    _args: &Vec<TypeVar>,
+    args: &Vec<TypeVar>,
 ) -> GenericResult<TypeVar> {
-    let type_var = resolver.create_type_var();
-    resolver.add_constraint(Constraint::IsLiteral(type_var, 
-        Unresolved::Literal(TypecheckType::None)
-    ))?;
-    Ok(type_var)
+    for arg in args {
+        resolver.add_constraint(Constraint::HasClass(*arg, type_classes::print()))?;
+    }
+    typecheck_literals(resolver, &vec![], &[], TypecheckType::None)
 }
 
 pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
     call_function(context, "print", args)
 }
 
-#[no_mangle]
-pub extern "C" fn print_map(map: *mut HashMap<String, bool>) {
-    let map_unpacked = unsafe { &*map };
-    // the pointer must be returned back into the general pool,
-    // by calling into raw.
-    print!("{{");
-    for (k, v) in &*map_unpacked {
-        print!("{}: {}, ", k, v);
-    }
-    print!("}}");
-}
-
 #[no_mangle]
 pub extern "C" fn print_string(value: *const c_char) {
     print!("{}", unsafe { CStr::from_ptr(value).to_str().unwrap() });
@@ -89,3 +76,10 @@ pub extern "C" fn print_bool(value: bool) {
 pub extern "C" fn print_int(value: i64) {
     print!("{}", value);
 }
+
+#[no_mangle]
+pub extern "C" fn print_float(value: f64) {
+    // floats are always printed with a decimal
+    // point, to tell them apart from ints.
+    print!("{:?}", value);
+}
@@ -16,10 +16,9 @@ fn boostrap_compiler(compiler: &mut Compiler) {
 fn typecheck(
     resolver: &mut TypeResolver<TypecheckType>,
     _function: &TypevarFunction,
-    _args: &Vec<TypeVar>,
+    args: &Vec<TypeVar>,
 ) -> GenericResult<TypeVar> {
-    let type_var = resolver.create_type_var();
-    Ok(type_var)
+    typecheck_literals(resolver, args, &[], TypecheckType::String)
 }
 
 pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
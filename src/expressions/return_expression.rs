@@ -31,8 +31,12 @@ pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
         )));
     }
     let result = gen_token(context, &args[0])?;
-    context.add_instruction(LLVMInstruction::BuildRet {
-        source: result.index,
+    let result = copy_returned_array(context, result)?;
+    context.add_instruction(match result.object_type {
+        Type::None => LLVMInstruction::BuildRetVoid,
+        _ => LLVMInstruction::BuildRet {
+            source: result.index,
+        },
     });
     Ok(result)
 }
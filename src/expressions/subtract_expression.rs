@@ -10,21 +10,52 @@ pub fn expression() -> Expression {
 
 fn boostrap_compiler(_compiler: &mut Compiler) {}
 
+/// a single argument is negated.
 fn typecheck(
     resolver: &mut TypeResolver<TypecheckType>,
     _function: &TypevarFunction,
     args: &Vec<TypeVar>,
 ) -> GenericResult<TypeVar> {
-    // TODO: figure out how to recurse into nested
-    // data structure type variables.
-    resolver.add_constraint(Constraint::Equality(args[1].clone(), args[0].clone()))?;
-    resolver.add_constraint(Constraint::IsLiteral(
-        args[0].clone(),
-        Unresolved::Literal(TypecheckType::Int),
-    ));
-    Ok(args[0].clone())
+    if args.len() == 1 {
+        resolver.add_constraint(Constraint::HasClass(args[0], type_classes::add()))?;
+        return Ok(args[0]);
+    }
+    type_classes::typecheck_class(resolver, args, type_classes::add())
 }
 
 pub fn codegen(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
-    codegen_binop(context, args, LLVMOpcode::LLVMSub)
+    if args.len() == 1 {
+        return codegen_negate(context, &args[0]);
+    }
+    codegen_binop(context, args, opcode)
+}
+
+fn opcode(typ: &Type) -> LLVMOpcode {
+    match typ {
+        Type::Float => LLVMOpcode::LLVMFSub,
+        _ => LLVMOpcode::LLVMSub,
+    }
+}
+
+/// negate the value, by subtracting it from zero.
+fn codegen_negate(context: &mut Context, arg: &Token) -> CodegenResult<Object> {
+    let value = gen_token(context, arg)?;
+    let zero = match value.object_type {
+        // -0.0, so that negating 0.0 flips its sign.
+        Type::Float => {
+            let zero = context.allocate(Type::Float);
+            context.add_instruction(LLVMInstruction::ConstFloat {
+                value: -0.0,
+                target: zero.index,
+            });
+            zero
+        }
+        ref typ => const_zero(context, typ),
+    };
+    Ok(build_binop(
+        context,
+        opcode(&value.object_type),
+        &zero,
+        &value,
+    ))
 }
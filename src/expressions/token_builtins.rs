@@ -0,0 +1,109 @@
+/// Native functions to inspect and build tokens.
+/// These are used by procedural macros, which manipulate
+/// tokens as opaque pointers.
+use super::utils::runtime_error;
+use super::{own_token, Token};
+use libc::c_char;
+use std::ffi::{CStr, CString};
+
+fn to_str<'a>(value: *const c_char) -> &'a str {
+    unsafe { CStr::from_ptr(value).to_str().unwrap() }
+}
+
+/// allocate the token for compiled code. Within a procedural
+/// macro, the token is freed once the macro returns.
+pub fn token_to_ptr(token: Token) -> *mut Token {
+    let token = Box::into_raw(Box::new(token));
+    own_token(token);
+    token
+}
+
+#[no_mangle]
+pub extern "C" fn token_is(token: *mut Token, kind: *const c_char) -> bool {
+    let token = unsafe { (*token).unlocated() };
+    match (to_str(kind), token) {
+        ("block", Token::Block(_)) => true,
+        ("boolean", Token::Boolean(_)) => true,
+        ("bytes", Token::Bytes(_)) => true,
+        ("expression", Token::Expression(_)) => true,
+        ("float", Token::Float(_)) => true,
+        ("integer", Token::Integer(_)) => true,
+        ("list", Token::List(_)) => true,
+        ("map", Token::Map(_)) => true,
+        ("none", Token::None) => true,
+        ("string", Token::String(_)) => true,
+        ("symbol", Token::Symbol(_)) => true,
+        _ => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn token_len(token: *mut Token) -> i64 {
+    match unsafe { (*token).unlocated() } {
+        Token::Block(ref tokens) | Token::Expression(ref tokens) | Token::List(ref tokens) => {
+            tokens.len() as i64
+        }
+        Token::Map(ref map) => map.len() as i64,
+        _ => 0,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn token_get(token: *mut Token, index: i64) -> *mut Token {
+    match unsafe { (*token).unlocated() } {
+        Token::Block(ref tokens) | Token::Expression(ref tokens) | Token::List(ref tokens) => {
+            if index < 0 || index as usize >= tokens.len() {
+                runtime_error(&format!(
+                    "index {} is out of bounds of a token of length {}",
+                    index,
+                    tokens.len()
+                ));
+            }
+            token_to_ptr(tokens[index as usize].clone())
+        }
+        t => runtime_error(&format!("unable to get index {} of token {}", index, t)),
+    }
+}
+
+/// append the item to the token, returning the token
+/// that was appended to.
+#[no_mangle]
+pub extern "C" fn token_push(token: *mut Token, item: *mut Token) -> *mut Token {
+    let item = unsafe { (*item).clone() };
+    match unsafe { (*token).unlocated_mut() } {
+        Token::Block(ref mut tokens)
+        | Token::Expression(ref mut tokens)
+        | Token::List(ref mut tokens) => tokens.push(item),
+        t => runtime_error(&format!("unable to append to token {}", t)),
+    }
+    token
+}
+
+/// create an empty block, expression or list.
+#[no_mangle]
+pub extern "C" fn make_token(kind: *const c_char) -> *mut Token {
+    token_to_ptr(match to_str(kind) {
+        "block" => Token::Block(vec![]),
+        "expression" => Token::Expression(vec![]),
+        "list" => Token::List(vec![]),
+        kind => runtime_error(&format!("unable to make token of kind {}", kind)),
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn make_symbol(name: *const c_char) -> *mut Token {
+    token_to_ptr(Token::Symbol(Box::new(to_str(name).to_owned())))
+}
+
+#[no_mangle]
+pub extern "C" fn symbol_name(token: *mut Token) -> *const c_char {
+    match unsafe { (*token).unlocated() } {
+        Token::Symbol(ref s) => CString::new(s.as_bytes()).unwrap().into_raw(),
+        t => runtime_error(&format!("token {} is not a symbol", t)),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn print_token(token: *mut Token) {
+    print!("{}", unsafe { &*token });
+}
@@ -0,0 +1,302 @@
+/// The token api. Procedural macros use these builtins
+/// to inspect the tokens they were passed, and to build
+/// the tokens they return.
+use super::*;
+
+fn boostrap_compiler(compiler: &mut Compiler) {
+    add_function_to_compiler(
+        compiler,
+        "function-arity",
+        Type::Int,
+        &[Type::String],
+        "function_arity",
+    );
+    add_function_to_compiler(
+        compiler,
+        "function-exists",
+        Type::Bool,
+        &[Type::String],
+        "function_exists",
+    );
+    add_function_to_compiler(
+        compiler,
+        "function-signature",
+        Type::String,
+        &[Type::Token],
+        "function_signature",
+    );
+    add_function_to_compiler(
+        compiler,
+        "gensym",
+        Type::Token,
+        &[Type::String],
+        "macro_gensym",
+    );
+    add_function_to_compiler(
+        compiler,
+        "macro-argument",
+        Type::Token,
+        &[],
+        "macro_argument",
+    );
+    add_function_to_compiler(
+        compiler,
+        "macro-result",
+        Type::None,
+        &[Type::Token],
+        "macro_result",
+    );
+    add_function_to_compiler(
+        compiler,
+        "make-symbol",
+        Type::Token,
+        &[Type::String],
+        "make_symbol",
+    );
+    add_function_to_compiler(
+        compiler,
+        "make-token",
+        Type::Token,
+        &[Type::String],
+        "make_token",
+    );
+    add_function_to_compiler(compiler, "print", Type::None, &[Type::Token], "print_token");
+    add_function_to_compiler(
+        compiler,
+        "symbol-name",
+        Type::String,
+        &[Type::Token],
+        "symbol_name",
+    );
+    add_function_to_compiler(
+        compiler,
+        "token-get",
+        Type::Token,
+        &[Type::Token, Type::Int],
+        "token_get",
+    );
+    add_function_to_compiler(
+        compiler,
+        "token-is",
+        Type::Bool,
+        &[Type::Token, Type::String],
+        "token_is",
+    );
+    add_function_to_compiler(
+        compiler,
+        "token-len",
+        Type::Int,
+        &[Type::Token],
+        "token_len",
+    );
+    add_function_to_compiler(
+        compiler,
+        "token-push",
+        Type::Token,
+        &[Type::Token, Type::Token],
+        "token_push",
+    );
+    add_function_to_compiler(compiler, "type-of", Type::String, &[Type::Token], "type_of");
+}
+
+/// the number of arguments of a function.
+pub fn function_arity() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(resolver, args, &[TypecheckType::String], TypecheckType::Int)
+        },
+        codegen: |context, args| call_function(context, "function-arity", args),
+    }
+}
+
+/// whether a function or builtin exists.
+pub fn function_exists() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::String],
+                TypecheckType::Bool,
+            )
+        },
+        codegen: |context, args| call_function(context, "function-exists", args),
+    }
+}
+
+/// the inferred signature of the function called by
+/// a call expression, such as "[Int Int] -> Int".
+pub fn function_signature() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::Token],
+                TypecheckType::String,
+            )
+        },
+        codegen: |context, args| call_function(context, "function-signature", args),
+    }
+}
+
+/// return a symbol that is unique across the program.
+pub fn gensym() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::String],
+                TypecheckType::Token,
+            )
+        },
+        codegen: |context, args| call_function(context, "gensym", args),
+    }
+}
+
+/// return the next argument passed to the macro
+/// being expanded.
+pub fn macro_argument() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(resolver, args, &[], TypecheckType::Token)
+        },
+        codegen: |context, args| call_function(context, "macro-argument", args),
+    }
+}
+
+/// set the token the macro being expanded resolves to.
+pub fn macro_result() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(resolver, args, &[TypecheckType::Token], TypecheckType::None)
+        },
+        codegen: |context, args| call_function(context, "macro-result", args),
+    }
+}
+
+pub fn make_symbol() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::String],
+                TypecheckType::Token,
+            )
+        },
+        codegen: |context, args| call_function(context, "make-symbol", args),
+    }
+}
+
+/// create an empty "block", "expression" or "list".
+pub fn make_token() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::String],
+                TypecheckType::Token,
+            )
+        },
+        codegen: |context, args| call_function(context, "make-token", args),
+    }
+}
+
+pub fn symbol_name() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::Token],
+                TypecheckType::String,
+            )
+        },
+        codegen: |context, args| call_function(context, "symbol-name", args),
+    }
+}
+
+pub fn token_get() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::Token, TypecheckType::Int],
+                TypecheckType::Token,
+            )
+        },
+        codegen: |context, args| call_function(context, "token-get", args),
+    }
+}
+
+/// check if the token is of a kind, such as
+/// "symbol" or "expression".
+pub fn token_is() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::Token, TypecheckType::String],
+                TypecheckType::Bool,
+            )
+        },
+        codegen: |context, args| call_function(context, "token-is", args),
+    }
+}
+
+pub fn token_len() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(resolver, args, &[TypecheckType::Token], TypecheckType::Int)
+        },
+        codegen: |context, args| call_function(context, "token-len", args),
+    }
+}
+
+pub fn token_push() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::Token, TypecheckType::Token],
+                TypecheckType::Token,
+            )
+        },
+        codegen: |context, args| call_function(context, "token-push", args),
+    }
+}
+
+/// the inferred type of an expression, such as "Int".
+pub fn type_of() -> Expression {
+    Expression {
+        boostrap_compiler: boostrap_compiler,
+        typecheck: |resolver, _, args| {
+            typecheck_literals(
+                resolver,
+                args,
+                &[TypecheckType::Token],
+                TypecheckType::String,
+            )
+        },
+        codegen: |context, args| call_function(context, "type-of", args),
+    }
+}
@@ -0,0 +1,162 @@
+/// The classes of types that builtins are overloaded over.
+/// Each class lists the types the builtin can generate code
+/// for, so any other type is a type error.
+use super::*;
+
+/// every integer type: Int, and the integers of a fixed size.
+pub fn integers() -> Vec<TypecheckType> {
+    vec![
+        TypecheckType::Int,
+        TypecheckType::I8,
+        TypecheckType::I16,
+        TypecheckType::I32,
+        TypecheckType::U8,
+        TypecheckType::U64,
+    ]
+}
+
+/// types that can be added and subtracted.
+pub fn add() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.push(TypecheckType::Float);
+    TypeClass::new("Add", types)
+}
+
+/// types that can be multiplied and divided.
+pub fn multiply() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.push(TypecheckType::Float);
+    TypeClass::new("Multiply", types)
+}
+
+/// types that are ordered, and can be compared with < and >.
+pub fn ord() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.extend(vec![TypecheckType::Byte, TypecheckType::Float]);
+    TypeClass::new("Ord", types)
+}
+
+/// types that support the bitwise and, or and xor.
+pub fn bits() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.push(TypecheckType::Bool);
+    TypeClass::new("Bits", types)
+}
+
+/// types that can be shifted left and right.
+pub fn shift() -> TypeClass<TypecheckType> {
+    TypeClass::new("Shift", integers())
+}
+
+/// types that can be compared with eq.
+pub fn eq() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.extend(vec![
+        TypecheckType::Bool,
+        TypecheckType::Byte,
+        TypecheckType::Float,
+    ]);
+    TypeClass::new("Eq", types)
+}
+
+/// types that can be converted to an int.
+pub fn to_int() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.extend(vec![TypecheckType::Float, TypecheckType::String]);
+    TypeClass::new("ToInt", types)
+}
+
+/// types that can be converted to a float.
+pub fn to_float() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.extend(vec![TypecheckType::Float, TypecheckType::String]);
+    TypeClass::new("ToFloat", types)
+}
+
+/// types that can be converted to an integer
+/// of a fixed size, e.g. ToU8.
+pub fn to_sized(typ: &TypecheckType) -> TypeClass<TypecheckType> {
+    TypeClass::new(&format!("To{:?}", typ), integers())
+}
+
+/// types that have a native print function.
+pub fn print() -> TypeClass<TypecheckType> {
+    // maps and byte arrays are printed natively, which
+    // limits the types they may contain.
+    let map_entries = TypeClass::new(
+        "Print",
+        vec![
+            TypecheckType::Bool,
+            TypecheckType::Int,
+            TypecheckType::String,
+        ],
+    );
+    let bytes = TypeClass::new("Print", vec![TypecheckType::Byte]);
+    let mut types = integers();
+    types.extend(vec![
+        TypecheckType::Bool,
+        TypecheckType::Byte,
+        TypecheckType::Float,
+        TypecheckType::String,
+    ]);
+    TypeClass::new("Print", types)
+        .with_generic(TypecheckType::Array, vec![bytes])
+        .with_generic(TypecheckType::Map, vec![map_entries.clone(), map_entries])
+}
+
+/// types that can be the keys and values of a map.
+pub fn map_entry() -> TypeClass<TypecheckType> {
+    TypeClass::new(
+        "MapEntry",
+        vec![
+            TypecheckType::Bool,
+            TypecheckType::Int,
+            TypecheckType::String,
+        ],
+    )
+}
+
+/// types that a match without a default arm can be on. A
+/// match on Bool must have an arm for both of its values.
+pub fn partial_match() -> TypeClass<TypecheckType> {
+    let mut types = integers();
+    types.extend(vec![TypecheckType::Byte, TypecheckType::Float]);
+    TypeClass::new("PartialMatch", types)
+}
+
+/// constrain the two arguments of a binary builtin to be
+/// of the same type, which is a member of the class. The
+/// result is of the same type as well.
+pub fn typecheck_class(
+    resolver: &mut TypeResolver<TypecheckType>,
+    args: &Vec<TypeVar>,
+    class: TypeClass<TypecheckType>,
+) -> GenericResult<TypeVar> {
+    if args.len() != 2 {
+        return Err(Box::new(DispError::new(&format!(
+            "expected 2 arguments, found {}",
+            args.len()
+        ))));
+    }
+    resolver.add_constraint(Constraint::HasClass(args[0], class.clone()))?;
+    resolver.add_constraint(Constraint::HasClass(args[1], class))?;
+    resolver.add_constraint(Constraint::Equality(args[0], args[1]))?;
+    Ok(args[0])
+}
+
+/// constrain the single argument of a conversion
+/// to be a member of the class.
+pub fn typecheck_conversion(
+    resolver: &mut TypeResolver<TypecheckType>,
+    args: &Vec<TypeVar>,
+    class: TypeClass<TypecheckType>,
+) -> GenericResult<()> {
+    if args.len() != 1 {
+        return Err(Box::new(DispError::new(&format!(
+            "expected 1 argument, found {}",
+            args.len()
+        ))));
+    }
+    resolver.add_constraint(Constraint::HasClass(args[0], class))?;
+    Ok(())
+}
@@ -1,10 +1,120 @@
 use super::*;
+use std::io::{self, Write};
+use std::process;
 
+/// generate a binary operation, with the opcode
+/// for the type of the arguments.
 pub fn codegen_binop(
     context: &mut Context,
     args: &[Token],
-    op: LLVMOpcode,
+    opcode: fn(&Type) -> LLVMOpcode,
 ) -> CodegenResult<Object> {
+    let (lhs, rhs) = gen_binop_args(context, args)?;
+    Ok(build_binop(context, opcode(&lhs.object_type), &lhs, &rhs))
+}
+
+/// generate a division, with the opcode for the type of the
+/// arguments. Dividing an integer by zero, or the smallest
+/// signed integer by -1, is a runtime error.
+pub fn codegen_division(
+    context: &mut Context,
+    args: &[Token],
+    opcode: fn(&Type) -> LLVMOpcode,
+) -> CodegenResult<Object> {
+    let (lhs, rhs) = gen_binop_args(context, args)?;
+    if rhs.object_type != Type::Float {
+        check_not_zero(context, &rhs)?;
+        if !is_unsigned(&rhs.object_type) {
+            check_no_overflow(context, &lhs, &rhs)?;
+        }
+    }
+    Ok(build_binop(context, opcode(&lhs.object_type), &lhs, &rhs))
+}
+
+/// generate a shift, with the opcode for the type of the arguments.
+/// Shifting by a negative amount, or by the number of bits of the
+/// integer or more, is a runtime error.
+pub fn codegen_shift(
+    context: &mut Context,
+    args: &[Token],
+    opcode: fn(&Type) -> LLVMOpcode,
+) -> CodegenResult<Object> {
+    let (lhs, rhs) = gen_binop_args(context, args)?;
+    // negative amounts are out of range once compared as unsigned.
+    let bits = context.const_int(bit_width(&rhs.object_type));
+    let amount = widen_integer(context, rhs.clone());
+    let out_of_range = context.allocate(Type::Bool);
+    context.add_instruction(LLVMInstruction::BuildICmp {
+        predicate: LLVMIntPredicate::LLVMIntUGE,
+        lhs: amount.index,
+        rhs: bits.index,
+        target: out_of_range.index,
+    });
+    exit_if(context, &out_of_range, "shift-out-of-range", &[])?;
+    Ok(build_binop(context, opcode(&lhs.object_type), &lhs, &rhs))
+}
+
+/// the number of bits of an integer type.
+fn bit_width(typ: &Type) -> i64 {
+    match typ {
+        Type::I8 | Type::U8 => 8,
+        Type::I16 => 16,
+        Type::I32 => 32,
+        _ => 64,
+    }
+}
+
+/// generate a comparison of the arguments, with the
+/// predicate for their type. The result is a Bool.
+pub fn codegen_comparison(
+    context: &mut Context,
+    args: &[Token],
+    signed: LLVMIntPredicate,
+    unsigned: LLVMIntPredicate,
+    float: LLVMRealPredicate,
+) -> CodegenResult<Object> {
+    let (lhs, rhs) = gen_binop_args(context, args)?;
+    Ok(build_comparison(
+        context, &lhs, &rhs, signed, unsigned, float,
+    ))
+}
+
+/// compare the objects, with the predicate for their type.
+pub fn build_comparison(
+    context: &mut Context,
+    lhs: &Object,
+    rhs: &Object,
+    signed: LLVMIntPredicate,
+    unsigned: LLVMIntPredicate,
+    float: LLVMRealPredicate,
+) -> Object {
+    let result = context.allocate(Type::Bool);
+    context.add_instruction(match lhs.object_type {
+        Type::Float => LLVMInstruction::BuildFCmp {
+            predicate: float,
+            lhs: lhs.index,
+            rhs: rhs.index,
+            target: result.index,
+        },
+        ref typ => LLVMInstruction::BuildICmp {
+            predicate: if is_unsigned(typ) { unsigned } else { signed },
+            lhs: lhs.index,
+            rhs: rhs.index,
+            target: result.index,
+        },
+    });
+    result
+}
+
+/// return true if values of the type are unsigned integers.
+pub fn is_unsigned(typ: &Type) -> bool {
+    match typ {
+        Type::Bool | Type::Byte | Type::U8 | Type::U64 => true,
+        _ => false,
+    }
+}
+
+fn gen_binop_args(context: &mut Context, args: &[Token]) -> CodegenResult<(Object, Object)> {
     if args.len() != 2 {
         return Err(CodegenError::new(&format!(
             "binary expression should only have two arguments. found {}",
@@ -13,16 +123,257 @@ pub fn codegen_binop(
     };
     let lhs = gen_token(context, &args[0])?;
     let rhs = gen_token(context, &args[1])?;
-    let result = context.allocate(Type::Int);
+    Ok((lhs, rhs))
+}
+
+pub fn build_binop(
+    context: &mut Context,
+    opcode: LLVMOpcode,
+    lhs: &Object,
+    rhs: &Object,
+) -> Object {
+    let result = context.allocate(lhs.object_type.clone());
     context.add_instruction(LLVMInstruction::BuildBinOp {
-        opcode: op,
+        opcode: opcode,
         lhs: lhs.index,
         rhs: rhs.index,
         target: result.index,
     });
+    result
+}
+
+/// branch to a runtime error if the integer is zero.
+fn check_not_zero(context: &mut Context, value: &Object) -> CodegenResult<()> {
+    let zero = const_zero(context, &value.object_type);
+    let is_zero = context.allocate(Type::Bool);
+    context.add_instruction(LLVMInstruction::BuildICmp {
+        predicate: LLVMIntPredicate::LLVMIntEQ,
+        lhs: value.index,
+        rhs: zero.index,
+        target: is_zero.index,
+    });
+    exit_if(context, &is_zero, "division-by-zero", &[])
+}
+
+/// branch to a runtime error if the signed division overflows, which
+/// only happens when the smallest integer is divided by -1.
+fn check_no_overflow(context: &mut Context, lhs: &Object, rhs: &Object) -> CodegenResult<()> {
+    let min = context.const_int(i64::min_value() >> (64 - bit_width(&lhs.object_type)));
+    let minus_one = context.const_int(-1);
+    let lhs = widen_integer(context, lhs.clone());
+    let rhs = widen_integer(context, rhs.clone());
+    let is_min = context.allocate(Type::Bool);
+    context.add_instruction(LLVMInstruction::BuildICmp {
+        predicate: LLVMIntPredicate::LLVMIntEQ,
+        lhs: lhs.index,
+        rhs: min.index,
+        target: is_min.index,
+    });
+    let is_minus_one = context.allocate(Type::Bool);
+    context.add_instruction(LLVMInstruction::BuildICmp {
+        predicate: LLVMIntPredicate::LLVMIntEQ,
+        lhs: rhs.index,
+        rhs: minus_one.index,
+        target: is_minus_one.index,
+    });
+    let overflows = build_binop(context, LLVMOpcode::LLVMAnd, &is_min, &is_minus_one);
+    exit_if(context, &overflows, "division-overflow", &[])
+}
+
+/// branch to a runtime error if the float does not fit in an Int:
+/// if it is NaN, infinite, or out of the range of an Int.
+fn check_fits_int(context: &mut Context, value: &Object) -> CodegenResult<()> {
+    // -2^63 is the smallest Int, while 2^63 is one
+    // more than the largest. Comparisons that are
+    // unordered are true if the float is NaN.
+    let min = context.const_float(i64::min_value() as f64);
+    let max = context.const_float(-(i64::min_value() as f64));
+    let below_min = context.allocate(Type::Bool);
+    context.add_instruction(LLVMInstruction::BuildFCmp {
+        predicate: LLVMRealPredicate::LLVMRealULT,
+        lhs: value.index,
+        rhs: min.index,
+        target: below_min.index,
+    });
+    let above_max = context.allocate(Type::Bool);
+    context.add_instruction(LLVMInstruction::BuildFCmp {
+        predicate: LLVMRealPredicate::LLVMRealUGE,
+        lhs: value.index,
+        rhs: max.index,
+        target: above_max.index,
+    });
+    let out_of_range = build_binop(context, LLVMOpcode::LLVMOr, &below_min, &above_max);
+    exit_if(context, &out_of_range, "int-out-of-range", &[value.clone()])
+}
+
+/// call the native function if the condition is true. It exits
+/// the program, so the code generated after this is only reached
+/// if the condition is false.
+fn exit_if(
+    context: &mut Context,
+    condition: &Object,
+    error_function: &str,
+    args: &[Object],
+) -> CodegenResult<()> {
+    let error_block = context.create_block("runtime_error".to_owned());
+    let continue_block = context.create_block("continue".to_owned());
+    context.add_instruction(LLVMInstruction::BuildCondBr {
+        value: condition.index,
+        true_block: error_block,
+        false_block: continue_block,
+    });
+    context.block = error_block;
+    call_function_with_objects(context, error_function, args)?;
+    context.add_instruction(LLVMInstruction::BuildBr {
+        block: continue_block,
+    });
+    context.block = continue_block;
+    Ok(())
+}
+
+/// merge the values of branches into the value of an expression.
+/// Each value is paired with the unterminated block it was built in,
+/// which branches to the block after the branches. That block is
+/// created last, so that every value is built before it is used.
+/// Without a value, the result is None, and if no branch finishes,
+/// neither does the expression.
+pub fn merge_branches(
+    context: &mut Context,
+    name: &str,
+    values: Vec<(usize, Object)>,
+    has_value: bool,
+) -> CodegenResult<Object> {
+    let after_block = context.create_block(name.to_owned());
+    for (block, _) in &values {
+        context.block = *block;
+        context.add_instruction(LLVMInstruction::BuildBr { block: after_block });
+    }
+    context.block = after_block;
+    if values.is_empty() {
+        context.add_instruction(LLVMInstruction::BuildUnreachable);
+        return Ok(Object::none());
+    }
+    let result_type = match values.first() {
+        Some((_, value)) if has_value => value.object_type.clone(),
+        _ => Type::None,
+    };
+    let result = context.allocate(result_type.clone());
+    if result_type != Type::None {
+        let llvm_type = context.compiler.llvm.types.get(&result_type);
+        context.add_instruction(LLVMInstruction::BuildPhi {
+            llvm_type: llvm_type,
+            incoming: values
+                .iter()
+                .map(|(block, value)| (value.index, *block))
+                .collect(),
+            target: result.index,
+        });
+    }
     Ok(result)
 }
 
+/// the zero value of the type.
+pub fn const_zero(context: &mut Context, typ: &Type) -> Object {
+    let llvm_type = context.compiler.llvm.types.get(typ);
+    let zero = context.allocate(typ.clone());
+    context.add_instruction(LLVMInstruction::ConstNull {
+        llvm_type: llvm_type,
+        target: zero.index,
+    });
+    zero
+}
+
+/// convert the argument to the type. Numbers are cast, while
+/// strings are parsed by the native function of the name. So are
+/// integers that may not fit in the type, which is checked. A
+/// float that does not fit in an Int is a runtime error.
+pub fn codegen_conversion(
+    context: &mut Context,
+    args: &[Token],
+    target_type: Type,
+    func_name: &str,
+) -> CodegenResult<Object> {
+    if args.len() != 1 {
+        return Err(CodegenError::new(&format!(
+            "conversion should only have one argument. found {}",
+            args.len()
+        )));
+    };
+    let value = gen_token(context, &args[0])?;
+    if value.object_type == target_type {
+        return Ok(value);
+    }
+    let value = widen_integer(context, value);
+    let opcode = match (&value.object_type, &target_type) {
+        (source, target) if source == target => return Ok(value),
+        (Type::Float, Type::Int) => {
+            check_fits_int(context, &value)?;
+            LLVMOpcode::LLVMFPToSI
+        }
+        (Type::Int, Type::Float) => LLVMOpcode::LLVMSIToFP,
+        (Type::U64, Type::Float) => LLVMOpcode::LLVMUIToFP,
+        _ => return call_function_with_objects(context, func_name, &[value]),
+    };
+    Ok(build_cast(context, opcode, value, target_type))
+}
+
+/// extend an integer of a fixed size to 64 bits: signed integers
+/// to an Int, and unsigned integers to a U64.
+pub fn widen_integer(context: &mut Context, value: Object) -> Object {
+    let (opcode, target_type) = match value.object_type {
+        Type::I8 | Type::I16 | Type::I32 => (LLVMOpcode::LLVMSExt, Type::Int),
+        Type::U8 => (LLVMOpcode::LLVMZExt, Type::U64),
+        _ => return value,
+    };
+    build_cast(context, opcode, value, target_type)
+}
+
+fn build_cast(
+    context: &mut Context,
+    opcode: LLVMOpcode,
+    value: Object,
+    target_type: Type,
+) -> Object {
+    let llvm_type = context.compiler.llvm.types.get(&target_type);
+    let result = context.allocate(target_type);
+    context.add_instruction(LLVMInstruction::BuildCast {
+        opcode: opcode,
+        source: value.index,
+        llvm_type: llvm_type,
+        target: result.index,
+    });
+    result
+}
+
+/// a convenience method to typecheck builtins that
+/// accept and return literal types.
+pub fn typecheck_literals(
+    resolver: &mut TypeResolver<TypecheckType>,
+    args: &Vec<TypeVar>,
+    arg_types: &[TypecheckType],
+    return_type: TypecheckType,
+) -> GenericResult<TypeVar> {
+    if args.len() != arg_types.len() {
+        return Err(Box::new(DispError::new(&format!(
+            "expected {} arguments, found {}",
+            arg_types.len(),
+            args.len()
+        ))));
+    }
+    for (arg, arg_type) in args.iter().zip(arg_types) {
+        resolver.add_constraint(Constraint::IsLiteral(
+            *arg,
+            Unresolved::Literal(arg_type.clone()),
+        ))?;
+    }
+    let type_var = resolver.create_type_var();
+    resolver.add_constraint(Constraint::IsLiteral(
+        type_var,
+        Unresolved::Literal(return_type),
+    ))?;
+    Ok(type_var)
+}
+
 /// a convenience method to add a function to a
 /// context
 pub fn add_function_to_compiler(
@@ -78,23 +429,29 @@ pub fn call_function(
     func_name: &str,
     args: &[Token],
 ) -> CodegenResult<Object> {
-    let (argument_objects, argument_types) = {
-        let mut argument_objects = Vec::with_capacity(args.len());
-        let mut argument_types = Vec::with_capacity(args.len());
-        for arg in args {
-            let result = gen_token(context, arg)?;
-            argument_objects.push(result.index);
-            argument_types.push(result.object_type);
-        }
-        (argument_objects, argument_types)
-    };
+    let mut objects = Vec::with_capacity(args.len());
+    for arg in args {
+        objects.push(gen_token(context, arg)?);
+    }
+    call_function_with_objects(context, func_name, &objects)
+}
+
+/// call the function with arguments that
+/// were generated already.
+pub fn call_function_with_objects(
+    context: &mut Context,
+    func_name: &str,
+    args: &[Object],
+) -> CodegenResult<Object> {
+    let argument_objects: Vec<usize> = args.iter().map(|arg| arg.index).collect();
+    let argument_types: Vec<Type> = args.iter().map(|arg| arg.object_type.clone()).collect();
 
     // first, check the functions declared for a valid option
     if let Some(function_by_arg_count) = context.function_map.get(func_name) {
         if let Some(function) = function_by_arg_count.get(&argument_types) {
             let object = context.allocate(function.return_type.clone());
             context.add_instruction(LLVMInstruction::BuildCall {
-                name: func_name.to_owned(),
+                name: function_symbol(func_name, &argument_types),
                 args: argument_objects,
                 target: object.index,
             });
@@ -125,3 +482,12 @@ pub fn call_function(
         func_name, &argument_types
     )))
 }
+
+/// report an error of the running program, and exit. Native
+/// functions can not unwind into compiled code, so they can
+/// not panic.
+pub fn runtime_error(message: &str) -> ! {
+    io::stdout().flush().unwrap();
+    eprintln!("error: {}", message);
+    process::exit(1)
+}
@@ -0,0 +1,84 @@
+/// Format tokens back into disp source, using the
+/// indented syntax for blocks.
+use super::Token;
+
+#[cfg(test)]
+mod tests;
+
+const INDENT: &'static str = "\t";
+
+/// format the statements of a block, one per line.
+pub fn format_block(statements: &[Token], indent: usize) -> String {
+    let mut lines = vec![];
+    for statement in statements {
+        match statement.unlocated() {
+            // comments are parsed as None.
+            Token::None => {}
+            Token::Block(ref inner) => lines.push(format_block(inner, indent)),
+            s => lines.push(format_statement(s, indent)),
+        }
+    }
+    lines.join("\n")
+}
+
+/// format a statement. A block passed as the last
+/// argument is written as an indented block on
+/// the following lines.
+pub fn format_statement(token: &Token, indent: usize) -> String {
+    let prefix = INDENT.repeat(indent);
+    match token.unlocated() {
+        Token::Expression(ref tokens) if !tokens.is_empty() => {
+            let (last, head) = tokens.split_last().unwrap();
+            match last.unlocated() {
+                Token::Block(ref block) if !head.is_empty() && !block.is_empty() => format!(
+                    "{}{}\n{}",
+                    prefix,
+                    format_all(head),
+                    format_block(block, indent + 1)
+                ),
+                _ => format!("{}{}", prefix, format_all(tokens)),
+            }
+        }
+        t => format!("{}{}", prefix, format_token(t)),
+    }
+}
+
+/// format a token as it would be written within a line.
+pub fn format_token(token: &Token) -> String {
+    match token {
+        Token::BangSymbol(ref s) => format!("{}!", s),
+        Token::Block(ref tokens) => format!("<{}>", format_all(tokens)),
+        Token::Boolean(b) => format!("{}", b),
+        Token::Bytes(ref b) => format!("'{}'", b),
+        Token::Comment(ref c) => format!("#{}", c),
+        Token::Expression(ref tokens) => format!("({})", format_all(tokens)),
+        Token::Float(x) => format!("{:?}", x),
+        Token::Integer(i) => format!("{}", i),
+        Token::List(ref tokens) => format!("[{}]", format_all(tokens)),
+        Token::Located(_, ref token) => format_token(token),
+        Token::Map(ref map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "{}: {},",
+                        format_token(&key.as_token()),
+                        format_token(value)
+                    )
+                })
+                .collect();
+            format!("{{{}}}", entries.join(" "))
+        }
+        Token::None => String::from("None"),
+        Token::String(ref s) => format!("\"{}\"", s),
+        Token::Symbol(ref s) => (**s).clone(),
+    }
+}
+
+fn format_all(tokens: &[Token]) -> String {
+    tokens
+        .iter()
+        .map(format_token)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,49 @@
+use super::super::parse;
+use super::format_block;
+use super::Token;
+
+/// formatting and parsing the result should
+/// produce the same tokens.
+fn assert_round_trip(source: &str) {
+    let token = parse(source);
+    let formatted = match token {
+        Token::Block(ref statements) => format_block(statements, 0),
+        _ => panic!("expected a block"),
+    };
+    assert_eq!(parse(&formatted), token, "formatted source:\n{}", formatted);
+}
+
+#[test]
+fn test_format_expression() {
+    assert_round_trip("print (+ 1 2)");
+}
+
+#[test]
+fn test_format_literals() {
+    assert_round_trip("let x \"hello world\"\nlet y 'bytes'\nlet z None\nlet w true");
+}
+
+#[test]
+fn test_format_indented_block() {
+    assert_round_trip("while true\n\tprint 1\n\tprint (get [1 2] 0)");
+}
+
+#[test]
+fn test_format_nested_block() {
+    assert_round_trip("fn foo [x]\n\tmatch x {\n\t\ttrue: <(print 1) (print 2)>,\n\t}");
+}
+
+#[test]
+fn test_format_block_output() {
+    assert_eq!(
+        format_block(&[parse_statement("while true\n\tprint 1")], 0),
+        "while true\n\tprint 1"
+    );
+}
+
+fn parse_statement(source: &str) -> Token {
+    match parse(source) {
+        Token::Block(mut tokens) => tokens.remove(0),
+        t => t,
+    }
+}
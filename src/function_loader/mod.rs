@@ -1,4 +1,13 @@
-use super::{parse_macro, Compiler, DispError, DispResult, MacroMap, Token};
+mod modules;
+#[cfg(test)]
+mod tests;
+
+use self::modules::{export_macros, import_module};
+pub use self::modules::{link_modules, load_module};
+use super::{
+    apply_macros_to_function_map, expand_macro_call, parse_file, parse_macro, unmark, DispError,
+    DispResult, ExpansionContext, Location, Module, Token,
+};
 use std::collections::HashMap;
 use std::rc::Rc;
 
@@ -21,44 +30,102 @@ impl UnparsedFunction {
 pub type FunctionMap = HashMap<String, Rc<UnparsedFunction>>;
 
 /// consume tokens, subdividing them into function and macro declarations.
+/// Macros are added to the expansion context.
+///
+/// Top-level macro calls are expanded before anything else, so
+/// macros can declare functions and other macros. This is repeated
+/// until no top-level macro calls are left, as the expansion may
+/// produce more calls, or call a macro declared later on.
 pub fn parse_functions_and_macros(
-    _compiler: &mut Compiler,
+    context: &mut ExpansionContext,
     parent_token: Token,
-) -> DispResult<(FunctionMap, MacroMap)> {
+) -> DispResult<FunctionMap> {
     let mut function_map = HashMap::new();
-    let mut macro_map = MacroMap::new();
+    // every form is paired with the chain of macros
+    // that expanded into it.
+    let mut forms: Vec<(Token, Vec<String>)> = match parent_token {
+        Token::Block(tokens) => tokens.into_iter().map(|t| (t, vec![])).collect(),
+        t => vec![(t, vec![])],
+    };
     // instructions that are not a part of any function
     // are automatically added to the main function.
     let mut main_function_body = vec![];
-    if let Token::Block(tokens) = parent_token {
-        for token in tokens {
-            match token {
+    loop {
+        // whether a macro was expanded or declared, in which
+        // case the remaining forms must be checked again.
+        let mut changed = false;
+        for (form, chain) in forms {
+            let location = match form {
+                Token::Located(ref location, _) => Some((**location).clone()),
+                _ => None,
+            };
+            match form.unlocated().clone() {
                 // the only token we really need to parse out is the expression,
                 // since that's the only thing that can define a top-level function.
                 // everything else is part of the main function.
                 Token::Expression(e) => match e[0].clone() {
                     Token::Symbol(ref s) => {
-                        if **s == "fn" {
-                            let (name, function) = parse_function(e)?;
+                        if unmark(s) == "fn" {
+                            let (name, function) = parse_function(e, &location)?;
+                            context.declare_function(&name, &function);
                             function_map.insert(name, function);
-                        } else {
-                            main_function_body.push(Token::Expression(e));
+                            continue;
                         }
-                    }
-                    Token::BangSymbol(ref s) => {
-                        if **s == "macro" {
-                            let (name, macro_instance) = parse_macro(e)?;
-                            macro_map.insert(name, macro_instance);
+                        context.expansion_stack = chain.clone();
+                        context.current_location = location;
+                        let expansion = expand_macro_call(context, &e)?;
+                        context.expansion_stack.clear();
+                        context.current_location = None;
+                        if let Some(result) = expansion {
+                            changed = true;
+                            let mut result_chain = chain;
+                            result_chain.push(unmark(s).to_owned());
+                            match result.unlocated().clone() {
+                                // a block may contain multiple declarations.
+                                Token::Block(tokens) => main_function_body.extend(
+                                    tokens
+                                        .into_iter()
+                                        .map(|t| (locate_as(&result, t), result_chain.clone())),
+                                ),
+                                _ => main_function_body.push((result, result_chain)),
+                            }
                         } else {
-                            main_function_body.push(Token::Expression(e));
+                            main_function_body.push((form, chain));
                         }
                     }
-                    _ => main_function_body.push(Token::Expression(e)),
+                    Token::BangSymbol(ref s) => match s.as_str() {
+                        "macro" | "proc-macro" => {
+                            context.current_location = location.clone();
+                            let (name, mut macro_instance) = parse_macro(e)?;
+                            macro_instance.location = location;
+                            context.declare_macro(name, macro_instance)?;
+                            context.current_location = None;
+                            changed = true;
+                        }
+                        // imported macros may be used by any form
+                        "import" => {
+                            import_module(context, &e, location)?;
+                            changed = true;
+                        }
+                        "export" => {
+                            context.current_location = location;
+                            export_macros(context, &e)?;
+                            context.current_location = None;
+                        }
+                        _ => main_function_body.push((form, chain)),
+                    },
+                    _ => main_function_body.push((form, chain)),
                 },
-                t => main_function_body.push(t),
+                _ => main_function_body.push((form, chain)),
             }
         }
+        if !changed {
+            break;
+        }
+        forms = main_function_body;
+        main_function_body = vec![];
     }
+    let main_function_body = main_function_body.into_iter().map(|(t, _)| t).collect();
     function_map.insert(
         String::from("main"),
         Rc::new(UnparsedFunction::new(
@@ -66,10 +133,24 @@ pub fn parse_functions_and_macros(
             Token::Block(main_function_body),
         )),
     );
-    Ok((function_map, macro_map))
+    Ok(function_map)
 }
 
-fn parse_function(tokens: Vec<Token>) -> DispResult<(String, Rc<UnparsedFunction>)> {
+/// statements from a block returned by a macro, which are not
+/// located themselves, are located where the block is.
+fn locate_as(block: &Token, statement: Token) -> Token {
+    match (block, statement) {
+        (Token::Located(ref location, _), Token::Expression(e)) => {
+            Token::Located(location.clone(), Box::new(Token::Expression(e)))
+        }
+        (_, statement) => statement,
+    }
+}
+
+fn parse_function(
+    tokens: Vec<Token>,
+    location: &Option<Location>,
+) -> DispResult<(String, Rc<UnparsedFunction>)> {
     if tokens.len() != 4 {
         return Err(DispError::new(&format!(
             "A function declaration should have 4 tokens: fn <name> <args> <body>. found {} for {:?}",
@@ -78,8 +159,10 @@ fn parse_function(tokens: Vec<Token>) -> DispResult<(String, Rc<UnparsedFunction
         )));
     }
     let name = {
+        // functions declared by a macro are visible to
+        // the whole program.
         if let Token::Symbol(ref s) = tokens[1] {
-            s.clone()
+            Box::new(unmark(s).to_owned())
         } else {
             return Err(DispError::new(&format!(
                 "function name must be a symbol, found {}",
@@ -114,8 +197,12 @@ fn parse_function(tokens: Vec<Token>) -> DispResult<(String, Rc<UnparsedFunction
             )));
         }
     };
-    return Ok((
-        *name,
-        Rc::new(UnparsedFunction::new(args, tokens[3].clone())),
-    ));
+    let body = match (location, tokens[3].clone()) {
+        // a body on the line of the declaration is located there.
+        (Some(location), body @ Token::Expression(_)) => {
+            Token::Located(Box::new(location.clone()), Box::new(body))
+        }
+        (_, body) => body,
+    };
+    return Ok((*name, Rc::new(UnparsedFunction::new(args, body))));
 }
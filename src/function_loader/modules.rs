@@ -0,0 +1,155 @@
+/// Loading modules.
+///
+/// Every file is a module. A module is loaded the first time it is
+/// imported, from a file named after the module. The file is searched
+/// for in the directory of the importing file, followed by the
+/// working directory.
+use super::{
+    apply_macros_to_function_map, parse_file, parse_functions_and_macros, unmark, DispError,
+    DispResult, ExpansionContext, FunctionMap, Location, Module, Token, UnparsedFunction,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+const MODULE_EXTENSION: &'static str = "ds";
+
+/// load a module: its macros, and its functions with all
+/// macros expanded. The module is added to the loaded modules.
+pub fn load_module(context: &mut ExpansionContext, name: &str, program: Token) -> DispResult<()> {
+    let previous = context.begin_module(name)?;
+    if let Some(prelude) = context.prelude.clone() {
+        if prelude != name {
+            // the prelude is never searched for next
+            // to the importing file.
+            context.current_location = None;
+            load_dependency(context, &prelude)?;
+            context.import_prelude(&prelude)?;
+        }
+    }
+    let mut functions = parse_functions_and_macros(context, program)?;
+    apply_macros_to_function_map(context, &mut functions)?;
+    context.finish_module(previous, functions)
+}
+
+/// import! module [names]
+///
+/// import the macros exported by the module. Without a list
+/// of names, all exported macros are imported.
+pub fn import_module(
+    context: &mut ExpansionContext,
+    args: &[Token],
+    location: Option<Location>,
+) -> DispResult<()> {
+    let (module, names) = match args {
+        [_, Token::Symbol(ref module)] => (module, None),
+        [_, Token::Symbol(ref module), Token::List(ref names)] => {
+            (module, Some(symbol_names(names)?))
+        }
+        _ => {
+            return Err(DispError::new(&format!(
+                "an import requires a module name, and optionally a list of macros. found {:?}",
+                args
+            )))
+        }
+    };
+    let module = unmark(module).to_owned();
+    context.current_location = location.clone();
+    load_dependency(context, &module)?;
+    // loading the module replaces the location.
+    context.current_location = location;
+    context.import_macros(&module, names)?;
+    context.current_location = None;
+    Ok(())
+}
+
+/// export! names
+pub fn export_macros(context: &mut ExpansionContext, args: &[Token]) -> DispResult<()> {
+    for name in symbol_names(&args[1..])? {
+        context.export_macro(name);
+    }
+    Ok(())
+}
+
+fn symbol_names(tokens: &[Token]) -> DispResult<Vec<String>> {
+    let mut names = vec![];
+    for t in tokens {
+        match t {
+            Token::Symbol(ref s) => names.push(unmark(s).to_owned()),
+            t => {
+                return Err(DispError::new(&format!(
+                    "expected the name of a macro. found {}",
+                    t
+                )))
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn load_dependency(context: &mut ExpansionContext, name: &str) -> DispResult<()> {
+    if !context.is_loaded(name) {
+        let program = read_module(context, name)?;
+        load_module(context, name, program)?;
+    }
+    Ok(())
+}
+
+fn read_module(context: &ExpansionContext, name: &str) -> DispResult<Token> {
+    let file_name = format!("{}.{}", name, MODULE_EXTENSION);
+    let mut candidates = vec![];
+    if let Some(ref location) = context.current_location {
+        if let Some(directory) = Path::new(&*location.file).parent() {
+            candidates.push(directory.join(&file_name));
+        }
+    }
+    candidates.push(PathBuf::from(&file_name));
+    for path in candidates {
+        if let Ok(source) = fs::read_to_string(&path) {
+            return Ok(parse_file(&source, &path.to_string_lossy()));
+        }
+    }
+    Err(DispError::new(&format!(
+        "unable to find module {}: no file named {}",
+        name, file_name
+    )))
+}
+
+/// combine the functions of all modules into a single program.
+/// The top-level statements of every module are a part of main,
+/// in the order the modules were loaded. Functions share a single
+/// namespace, so two modules can not declare the same function.
+pub fn link_modules(modules: &[Module]) -> DispResult<FunctionMap> {
+    let mut function_map = FunctionMap::new();
+    let mut function_modules: HashMap<&str, &str> = HashMap::new();
+    let mut main_function_body = vec![];
+    for module in modules {
+        for (name, function) in &module.functions {
+            if name != "main" {
+                if let Some(other) = function_modules.insert(name, &module.name) {
+                    return Err(DispError::new(&format!(
+                        "function {} is declared in both module {} and module {}",
+                        name, other, module.name
+                    )));
+                }
+                function_map.insert(name.clone(), function.clone());
+                continue;
+            }
+            match function.body {
+                Token::Block(ref statements) => main_function_body.extend(statements.clone()),
+                ref t => main_function_body.push(t.clone()),
+            }
+        }
+    }
+    function_map.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(
+            vec![],
+            Token::Block(main_function_body),
+        )),
+    );
+    Ok(function_map)
+}
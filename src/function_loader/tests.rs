@@ -0,0 +1,235 @@
+use super::super::{apply_macros_to_function_map, parse, parse_file, DispError, MacroMap};
+use super::{
+    link_modules, load_module, parse_functions_and_macros, ExpansionContext, FunctionMap, Token,
+};
+
+fn load(source: &str) -> (ExpansionContext, FunctionMap) {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    let mut functions = parse_functions_and_macros(&mut context, parse(source)).unwrap();
+    apply_macros_to_function_map(&mut context, &mut functions).unwrap();
+    (context, functions)
+}
+
+#[test]
+fn test_macro_declares_function() {
+    let (_, functions) = load(
+        "macro! def-inc [name] (fn name [x] (+ x 1))
+def-inc inc
+inc 1",
+    );
+    assert_eq!(functions.get("inc").unwrap().args.len(), 1);
+    assert_eq!(functions.get("main").unwrap().body, parse("inc 1"));
+}
+
+#[test]
+fn test_macro_declares_macro() {
+    // the macro is used before it is declared, requiring
+    // another round of expansion.
+    let (context, functions) = load(
+        "def-neq neq
+neq 1 2
+macro! def-neq [name] (macro! name [l r] (not (eq l r)))",
+    );
+    assert!(context.macros.contains_key("neq"));
+    assert_eq!(functions.get("main").unwrap().body, parse("not (eq 1 2)"));
+}
+
+#[test]
+fn test_macro_declares_multiple_functions() {
+    let (_, functions) = load(
+        "macro! def-pair [first second] <(fn first [] (return 1)) (fn second [] (return 2))>
+def-pair one two",
+    );
+    assert!(functions.contains_key("one"));
+    assert!(functions.contains_key("two"));
+    assert_eq!(functions.get("main").unwrap().body, Token::Block(vec![]));
+}
+
+#[test]
+fn test_recursive_top_level_macro_exceeds_depth() {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    context.max_depth = 5;
+    let error = parse_functions_and_macros(
+        &mut context,
+        parse_file("macro! loop [x] (loop x)\nloop 1", "main.ds"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "macro expansion exceeded the maximum depth of 5. expansion chain: loop (x6)"
+    );
+    assert_eq!(
+        context.current_location.unwrap().describe(),
+        "at main.ds:1
+in expansion of `loop` at main.ds:1 (x4)
+in expansion of `loop` at main.ds:2"
+    );
+}
+
+#[test]
+fn test_expanded_tokens_are_located() {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    let functions = parse_functions_and_macros(
+        &mut context,
+        parse_file(
+            "macro! twice [x]
+\tprint x
+\tprint x
+macro! twice-one [] (twice 1)
+twice-one",
+            "t.ds",
+        ),
+    )
+    .unwrap();
+    let statements = match functions.get("main").unwrap().body {
+        Token::Block(ref statements) => statements.clone(),
+        ref t => panic!("expected a block, found {}", t),
+    };
+    assert_eq!(statements.len(), 2);
+    match statements[1] {
+        Token::Located(ref location, _) => assert_eq!(
+            location.describe(),
+            "at t.ds:3
+in expansion of `twice` at t.ds:4
+in expansion of `twice-one` at t.ds:5"
+        ),
+        ref t => panic!("expected a located token, found {}", t),
+    }
+}
+
+/// load the sources as modules, in order. The
+/// last module is loaded as main.
+fn load_modules(modules: &[(&str, &str)]) -> Result<FunctionMap, DispError> {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    for &(name, source) in modules {
+        load_module(&mut context, name, parse(source))?;
+    }
+    link_modules(&context.modules)
+}
+
+const UTIL_MODULE: &'static str = "export! inc
+macro! inc [x] (add-one x)
+macro! add-one [x] (+ x 1)";
+
+#[test]
+fn test_import_macro() {
+    let functions =
+        load_modules(&[("util", UTIL_MODULE), ("main", "import! util\ninc 1")]).unwrap();
+    // the macros used by inc do not need to be imported.
+    assert_eq!(functions.get("main").unwrap().body, parse("+ 1 1"));
+}
+
+#[test]
+fn test_macros_are_module_scoped() {
+    let functions = load_modules(&[("util", UTIL_MODULE), ("main", "inc 1")]).unwrap();
+    assert_eq!(functions.get("main").unwrap().body, parse("inc 1"));
+}
+
+#[test]
+fn test_import_unexported_macro() {
+    let error =
+        load_modules(&[("util", UTIL_MODULE), ("main", "import! util [add-one]")]).unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "module util does not export macro add-one"
+    );
+}
+
+#[test]
+fn test_import_collision() {
+    let error = load_modules(&[
+        ("util", UTIL_MODULE),
+        ("main", "macro! inc [x] (+ x 2)\nimport! util"),
+    ])
+    .unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "unable to import macro inc from module util: a macro named inc is already declared in module main"
+    );
+}
+
+#[test]
+fn test_function_declared_in_two_modules() {
+    let error = load_modules(&[
+        ("util", "fn inc [x] (+ x 1)"),
+        ("main", "fn inc [x] (+ x 2)\ninc 1"),
+    ])
+    .unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "function inc is declared in both module util and module main"
+    );
+}
+
+#[test]
+fn test_prelude_macros_can_be_shadowed() {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    load_module(&mut context, "util", parse(UTIL_MODULE)).unwrap();
+    context.prelude = Some(String::from("util"));
+    load_module(&mut context, "main", parse("macro! inc [x] (+ x 2)\ninc 1")).unwrap();
+    let functions = link_modules(&context.modules).unwrap();
+    assert_eq!(functions.get("main").unwrap().body, parse("+ 1 2"));
+}
+
+#[test]
+fn test_reflect_function_signature() {
+    let (_, functions) = load(
+        "proc-macro! signature-of [call] (return (make-symbol (function-signature call)))
+fn add [l r] (return (+ l r))
+signature-of (add 1 2)",
+    );
+    assert_eq!(
+        functions.get("main").unwrap().body,
+        Token::Block(vec![Token::Symbol(Box::new(String::from(
+            "[Int Int] -> Int"
+        )))])
+    );
+}
+
+#[test]
+fn test_reflect_type_of() {
+    let (_, functions) = load(
+        "proc-macro! type-name [expr] (return (make-symbol (type-of expr)))
+fn is-one [x] (return (eq x 1))
+type-name (is-one 2)",
+    );
+    assert_eq!(
+        functions.get("main").unwrap().body,
+        Token::Block(vec![Token::Symbol(Box::new(String::from("Bool")))])
+    );
+}
+
+#[test]
+fn test_reflect_function_arity() {
+    // repeats the name of the function once per argument.
+    let (_, functions) = load(
+        "proc-macro! repeat-arity [name]
+\tlet result (make-token \"list\")
+\tlet i (function-arity (symbol-name name))
+\twhile (not (eq i 0))
+\t\tlet i (- i 1)
+\t\ttoken-push result name
+\treturn result
+fn add [l r] (return (+ l r))
+repeat-arity add",
+    );
+    let add = Token::Symbol(Box::new(String::from("add")));
+    assert_eq!(
+        functions.get("main").unwrap().body,
+        Token::Block(vec![Token::List(vec![add.clone(), add])])
+    );
+}
+
+#[test]
+fn test_reflect_missing_function() {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    let source = "proc-macro! arity-of [name]
+\tfunction-arity (symbol-name name)
+\treturn name
+arity-of g";
+    let error = parse_functions_and_macros(&mut context, parse(source)).unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "procedural macro arity-of failed: function-arity: no function named g"
+    );
+}
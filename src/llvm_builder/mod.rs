@@ -136,6 +136,22 @@ impl Builder {
                             objects[*target] =
                                 LLVMBuildAlloca(self.builder, *llvm_type, to_ptr("alloca"));
                         }
+                        LLVMInstruction::BuildMalloc { llvm_type, target } => {
+                            objects[*target] =
+                                LLVMBuildMalloc(self.builder, *llvm_type, to_ptr("malloc"));
+                        }
+                        LLVMInstruction::BuildArrayMalloc {
+                            llvm_type,
+                            size,
+                            target,
+                        } => {
+                            objects[*target] = LLVMBuildArrayMalloc(
+                                self.builder,
+                                *llvm_type,
+                                objects[*size],
+                                to_ptr("malloc"),
+                            );
+                        }
                         LLVMInstruction::BuildBinOp {
                             opcode,
                             lhs,
@@ -153,6 +169,20 @@ impl Builder {
                         LLVMInstruction::BuildBr { block } => {
                             LLVMBuildBr(self.builder, basic_blocks[*block]);
                         }
+                        LLVMInstruction::BuildCast {
+                            opcode,
+                            source,
+                            llvm_type,
+                            target,
+                        } => {
+                            objects[*target] = LLVMBuildCast(
+                                self.builder,
+                                *opcode,
+                                objects[*source],
+                                *llvm_type,
+                                to_ptr("cast"),
+                            );
+                        }
                         LLVMInstruction::BuildCondBr {
                             value,
                             true_block,
@@ -190,12 +220,33 @@ impl Builder {
                             objects[*target] =
                                 LLVMBuildNot(self.builder, objects[*source], to_ptr("not"));
                         }
+                        LLVMInstruction::BuildPhi {
+                            llvm_type,
+                            incoming,
+                            target,
+                        } => {
+                            let phi = LLVMBuildPhi(self.builder, *llvm_type, to_ptr("phi"));
+                            let mut values: Vec<LLVMValueRef> =
+                                incoming.iter().map(|(value, _)| objects[*value]).collect();
+                            let mut blocks: Vec<LLVMBasicBlockRef> =
+                                incoming.iter().map(|(_, block)| basic_blocks[*block]).collect();
+                            LLVMAddIncoming(
+                                phi,
+                                values.as_mut_ptr(),
+                                blocks.as_mut_ptr(),
+                                incoming.len() as u32,
+                            );
+                            objects[*target] = phi;
+                        }
                         LLVMInstruction::BuildRet { source } => {
                             LLVMBuildRet(self.builder, objects[*source]);
                         }
                         LLVMInstruction::BuildRetVoid => {
                             LLVMBuildRetVoid(self.builder);
                         }
+                        LLVMInstruction::BuildUnreachable => {
+                            LLVMBuildUnreachable(self.builder);
+                        }
                         LLVMInstruction::BuildSwitch {
                             value,
                             post_switch_block,
@@ -209,13 +260,32 @@ impl Builder {
                                 *num_cases,
                             );
                         }
-                        LLVMInstruction::BuildICmp { lhs, rhs, target } => {
+                        LLVMInstruction::BuildICmp {
+                            predicate,
+                            lhs,
+                            rhs,
+                            target,
+                        } => {
                             objects[*target] = LLVMBuildICmp(
                                 self.builder,
-                                LLVMIntPredicate::LLVMIntEQ,
+                                *predicate,
+                                objects[*lhs],
+                                objects[*rhs],
+                                to_ptr("cmptemp"),
+                            );
+                        }
+                        LLVMInstruction::BuildFCmp {
+                            predicate,
+                            lhs,
+                            rhs,
+                            target,
+                        } => {
+                            objects[*target] = LLVMBuildFCmp(
+                                self.builder,
+                                *predicate,
                                 objects[*lhs],
                                 objects[*rhs],
-                                to_ptr("eqtemp"),
+                                to_ptr("cmptemp"),
                             );
                         }
                         LLVMInstruction::BuildStore { source, target } => {
@@ -225,13 +295,18 @@ impl Builder {
                             objects[*target] =
                                 LLVMConstInt(LLVMInt1Type(), if *value { 1 } else { 0 } as u64, 0);
                         }
+                        LLVMInstruction::ConstNull { llvm_type, target } => {
+                            objects[*target] = LLVMConstNull(*llvm_type);
+                        }
+                        LLVMInstruction::ConstFloat { value, target } => {
+                            objects[*target] =
+                                LLVMConstReal(LLVMDoubleTypeInContext(self.context), *value);
+                        }
                         LLVMInstruction::ConstInt { value, target } => {
                             objects[*target] = LLVMConstInt(
-                                // TODO: switch back to 64
-                                // once GEP is figured out
-                                LLVMInt32TypeInContext(self.context),
+                                LLVMInt64TypeInContext(self.context),
                                 *value as u64,
-                                0,
+                                1,
                             );
                         }
                         LLVMInstruction::ConstI32 { value, target } => {
@@ -319,6 +394,18 @@ pub enum LLVMInstruction {
         llvm_type: LLVMTypeRef,
         target: usize,
     },
+    /// allocate the type on the heap, for values
+    /// that outlive the function creating them.
+    BuildMalloc {
+        llvm_type: LLVMTypeRef,
+        target: usize,
+    },
+    /// allocate a number of values of the type on the heap.
+    BuildArrayMalloc {
+        llvm_type: LLVMTypeRef,
+        size: usize,
+        target: usize,
+    },
     BuildBinOp {
         opcode: LLVMOpcode,
         lhs: usize,
@@ -333,6 +420,12 @@ pub enum LLVMInstruction {
         args: Vec<usize>,
         target: usize,
     },
+    BuildCast {
+        opcode: LLVMOpcode,
+        source: usize,
+        llvm_type: LLVMTypeRef,
+        target: usize,
+    },
     BuildCondBr {
         value: usize,
         true_block: usize,
@@ -343,11 +436,18 @@ pub enum LLVMInstruction {
         indices: Vec<usize>,
         target: usize,
     },
+    BuildFCmp {
+        predicate: LLVMRealPredicate,
+        lhs: usize,
+        rhs: usize,
+        target: usize,
+    },
     BuildGlobalString {
         value: String,
         target: usize,
     },
     BuildICmp {
+        predicate: LLVMIntPredicate,
         lhs: usize,
         rhs: usize,
         target: usize,
@@ -370,14 +470,31 @@ pub enum LLVMInstruction {
         source: usize,
         target: usize,
     },
+    /// the value of the object from the block that
+    /// was branched from, for each (object, block).
+    BuildPhi {
+        llvm_type: LLVMTypeRef,
+        incoming: Vec<(usize, usize)>,
+        target: usize,
+    },
     BuildRet {
         source: usize,
     },
     BuildRetVoid,
+    BuildUnreachable,
     ConstBool {
         value: bool,
         target: usize,
     },
+    ConstFloat {
+        value: f64,
+        target: usize,
+    },
+    /// the zero value of the type.
+    ConstNull {
+        llvm_type: LLVMTypeRef,
+        target: usize,
+    },
     ConstInt {
         value: i64,
         target: usize,
@@ -401,6 +518,7 @@ impl LLVMInstruction {
         match self {
             LLVMInstruction::BuildRet { source: _ } => true,
             LLVMInstruction::BuildRetVoid {} => true,
+            LLVMInstruction::BuildUnreachable {} => true,
             LLVMInstruction::BuildBr { block: _ } => true,
             LLVMInstruction::BuildCondBr {
                 value: _,
@@ -1,6 +1,9 @@
 /// Utility Code For Creating Disp Arrays (array + fat pointer)
 /// in LLVM
-use super::{extract_type_from_pointer, CodegenResult, Context, LLVMInstruction, Object, Type};
+use super::{
+    extract_type_from_pointer, CodegenError, CodegenResult, Context, LLVMInstruction, Object, Type,
+};
+use llvm_sys::{LLVMIntPredicate, LLVMOpcode};
 
 /// array_value_pointer should not be an actual pointer, but the
 /// index in the scope in which the pointer actually lives.
@@ -55,3 +58,151 @@ pub fn create_array(
     // finally, return the object
     Ok(object)
 }
+
+/// arrays are allocated on the stack of the function creating
+/// them, so an array that is returned is copied to the heap, along
+/// with the arrays it contains. The copy is never freed.
+pub fn copy_returned_array(context: &mut Context, object: Object) -> CodegenResult<Object> {
+    match object.object_type {
+        Type::Array(_) => copy_array(context, &object),
+        _ => Ok(object),
+    }
+}
+
+fn copy_array(context: &mut Context, array: &Object) -> CodegenResult<Object> {
+    let subtype = match array.object_type {
+        Type::Array(ref subtype) => (**subtype).clone(),
+        ref other => {
+            return Err(CodegenError::new(&format!(
+                "type {:?} is not an array",
+                other
+            )));
+        }
+    };
+    let source = load_field(context, array, 0);
+    let length = load_field(context, array, 1);
+    let element_type = context.compiler.llvm.types.get(&subtype);
+    let elements = context.allocate_without_type();
+    context.add_instruction(LLVMInstruction::BuildArrayMalloc {
+        llvm_type: element_type,
+        size: length,
+        target: elements,
+    });
+    // copy each element, starting from the first.
+    let index = context.allocate_variable(Type::Int);
+    let zero = context.const_int(0);
+    context.add_instruction(LLVMInstruction::BuildStore {
+        source: zero.index,
+        target: index.index,
+    });
+    let condition_block = context.create_block("copy_condition".to_owned());
+    let copy_block = context.create_block("copy".to_owned());
+    context.add_instruction(LLVMInstruction::BuildBr {
+        block: condition_block,
+    });
+    context.block = copy_block;
+    let i = context.allocate(Type::Int);
+    context.add_instruction(LLVMInstruction::BuildLoad {
+        source: index.index,
+        target: i.index,
+    });
+    let source_pointer = context.allocate_without_type();
+    context.add_instruction(LLVMInstruction::BuildGEP {
+        value: source,
+        indices: vec![i.index],
+        target: source_pointer,
+    });
+    let element = context.allocate(subtype);
+    context.add_instruction(LLVMInstruction::BuildLoad {
+        source: source_pointer,
+        target: element.index,
+    });
+    let element = copy_returned_array(context, element)?;
+    let target_pointer = context.allocate_without_type();
+    context.add_instruction(LLVMInstruction::BuildGEP {
+        value: elements,
+        indices: vec![i.index],
+        target: target_pointer,
+    });
+    context.add_instruction(LLVMInstruction::BuildStore {
+        source: element.index,
+        target: target_pointer,
+    });
+    let one = context.const_int(1);
+    let next = context.allocate(Type::Int);
+    context.add_instruction(LLVMInstruction::BuildBinOp {
+        opcode: LLVMOpcode::LLVMAdd,
+        lhs: i.index,
+        rhs: one.index,
+        target: next.index,
+    });
+    context.add_instruction(LLVMInstruction::BuildStore {
+        source: next.index,
+        target: index.index,
+    });
+    context.add_instruction(LLVMInstruction::BuildBr {
+        block: condition_block,
+    });
+    let copied_block = context.create_block("copied".to_owned());
+    context.block = condition_block;
+    let i = context.allocate(Type::Int);
+    context.add_instruction(LLVMInstruction::BuildLoad {
+        source: index.index,
+        target: i.index,
+    });
+    let is_copying = context.allocate(Type::Bool);
+    context.add_instruction(LLVMInstruction::BuildICmp {
+        predicate: LLVMIntPredicate::LLVMIntSLT,
+        lhs: i.index,
+        rhs: length,
+        target: is_copying.index,
+    });
+    context.add_instruction(LLVMInstruction::BuildCondBr {
+        value: is_copying.index,
+        true_block: copy_block,
+        false_block: copied_block,
+    });
+    context.block = copied_block;
+    let struct_type =
+        extract_type_from_pointer(context.compiler.llvm.types.get(&array.object_type));
+    let object = context.allocate(array.object_type.clone());
+    context.add_instruction(LLVMInstruction::BuildMalloc {
+        llvm_type: struct_type,
+        target: object.index,
+    });
+    store_field(context, &object, 0, elements);
+    store_field(context, &object, 1, length);
+    Ok(object)
+}
+
+/// load a field of the array struct: the raw
+/// array first, followed by the length.
+fn load_field(context: &mut Context, array: &Object, field: i32) -> usize {
+    let field_pointer = field_pointer(context, array, field);
+    let value = context.allocate_without_type();
+    context.add_instruction(LLVMInstruction::BuildLoad {
+        source: field_pointer,
+        target: value,
+    });
+    value
+}
+
+fn store_field(context: &mut Context, array: &Object, field: i32, value: usize) {
+    let field_pointer = field_pointer(context, array, field);
+    context.add_instruction(LLVMInstruction::BuildStore {
+        source: value,
+        target: field_pointer,
+    });
+}
+
+fn field_pointer(context: &mut Context, array: &Object, field: i32) -> usize {
+    let zero = context.const_i32(0);
+    let field = context.const_i32(field);
+    let field_pointer = context.allocate_without_type();
+    context.add_instruction(LLVMInstruction::BuildGEP {
+        value: array.index,
+        indices: vec![zero.index, field.index],
+        target: field_pointer,
+    });
+    field_pointer
+}
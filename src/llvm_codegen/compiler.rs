@@ -1,7 +1,7 @@
 use super::{
-    create_array, extract_type_from_pointer, AnnotatedFunction, AnnotatedFunctionMap, BasicBlock,
-    CodegenError, CodegenResult, Compiler, Context, Function, FunctionType, LLVMInstruction,
-    Object, Scope, Token, Type,
+    copy_returned_array, create_array, extract_type_from_pointer, map_address, AnnotatedFunction,
+    AnnotatedFunctionMap, BasicBlock, CodegenError, CodegenResult, Compiler, Context, Function,
+    FunctionType, LLVMInstruction, Map, Object, Scope, Token, Type,
 };
 use llvm_sys::core::*;
 
@@ -13,18 +13,31 @@ pub fn build_functions(
     // to reallocate when the full map is available.
     let function_map = functions.clone();
     for (name, function_by_args) in functions {
-        for (_, function) in function_by_args {
+        for (arg_types, function) in function_by_args {
             if cfg!(feature = "debug") {
                 println!("building function {:?}", &function);
             }
+            let symbol = function_symbol(name, arg_types);
             let function =
-                FunctionType::Disp(build_function(&function_map, compiler, name, function)?);
-            compiler.data.functions.insert(name.to_string(), function);
+                FunctionType::Disp(build_function(&function_map, compiler, &symbol, function)?);
+            compiler.data.functions.insert(symbol, function);
         }
     }
     Ok(())
 }
 
+/// the name of the llvm function for the specialization of a
+/// function with the argument types. Each specialization is a
+/// separate llvm function. A function without arguments only
+/// has one, so it keeps its name.
+pub fn function_symbol(name: &str, arg_types: &[Type]) -> String {
+    if arg_types.is_empty() {
+        return name.to_owned();
+    }
+    let arg_types: Vec<String> = arg_types.iter().map(|t| t.to_string()).collect();
+    format!("{}({})", name, arg_types.join(", "))
+}
+
 fn build_function(
     function_map: &AnnotatedFunctionMap,
     compiler: &mut Compiler,
@@ -40,6 +53,7 @@ fn build_function(
         Some(source_function.return_type.clone()),
     );
     {
+        compiler.data.map_types = source_function.map_types.clone();
         let mut scope = Scope::new(None);
         let entry_block = function.create_block("entry".to_owned());
         let mut context = Context::new(
@@ -75,9 +89,25 @@ fn build_function(
                 .locals
                 .insert(source_function.function.args[i].clone(), param.clone());
         }
-        gen_token(&mut context, &source_function.function.body)?;
+        let result = gen_token(&mut context, &source_function.function.body)?;
+        // the last expression is returned, unless
+        // every path has returned already.
         if !context.current_block().has_been_terminated() {
-            context.add_instruction(LLVMInstruction::BuildRetVoid {});
+            let result = copy_returned_array(&mut context, result)?;
+            match source_function.return_type {
+                Type::None => context.add_instruction(LLVMInstruction::BuildRetVoid {}),
+                ref return_type if *return_type == result.object_type => {
+                    context.add_instruction(LLVMInstruction::BuildRet {
+                        source: result.index,
+                    })
+                }
+                ref return_type => {
+                    return Err(CodegenError::new(&format!(
+                        "missing return at the end of {}: expected {}, found {}",
+                        name, return_type, result.object_type
+                    )))
+                }
+            }
         }
     }
     Ok(function)
@@ -93,15 +123,7 @@ pub fn gen_token(context: &mut Context, token: &Token) -> CodegenResult<Object>
             });
             object
         }
-        &Token::Map(ref m) => {
-            let object = context.allocate(Type::Map(Box::new(Type::String), Box::new(Type::Int)));
-            context.add_instruction(LLVMInstruction::BuildCall {
-                name: String::from("create_map"),
-                args: vec![],
-                target: object.index,
-            });
-            object
-        }
+        &Token::Map(ref m) => gen_map(context, m)?,
         &Token::None => Object::none(),
         &Token::Bytes(ref s) => {
             // extract the proper subtypalex chance pove
@@ -139,10 +161,19 @@ pub fn gen_token(context: &mut Context, token: &Token) -> CodegenResult<Object>
                 }
             }
         }
+        &Token::Float(x) => context.const_float(x),
         &Token::Integer(i) => context.const_int(i),
         &Token::Block(ref tl) => gen_block(context, tl)?,
         &Token::List(ref tl) => gen_list(context, tl)?,
         &Token::Expression(ref tl) => gen_expr(context, tl)?,
+        &Token::Located(ref location, ref t) => {
+            // the location is only restored on success, so
+            // errors keep the innermost location.
+            let previous = context.compiler.data.location.replace((**location).clone());
+            let object = gen_token(context, t)?;
+            context.compiler.data.location = previous;
+            object
+        }
         _ => Object::none(),
     })
 }
@@ -194,6 +225,51 @@ fn gen_list(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
     )
 }
 
+fn gen_map(context: &mut Context, map: &Map) -> CodegenResult<Object> {
+    let mut entries = vec![];
+    for (key, value) in map.iter() {
+        entries.push((
+            gen_token(context, &key.as_token())?,
+            gen_token(context, value)?,
+        ));
+    }
+    // the types of an empty map depend on how it is
+    // used, so they are taken from the annotated types.
+    let map_type = match context.compiler.data.map_types.get(&map_address(map)) {
+        Some(map_type) => map_type.clone(),
+        None => {
+            return Err(CodegenError::new(
+                "unable to find the type of a map literal",
+            ))
+        }
+    };
+    let object = context.allocate(map_type.clone());
+    context.add_instruction(LLVMInstruction::BuildCall {
+        name: String::from("create_map"),
+        args: vec![],
+        target: object.index,
+    });
+    for (key, value) in entries {
+        let arg_types = [map_type.clone(), key.object_type, value.object_type];
+        let name = match context.get_function("insert", &arg_types) {
+            Some(name) => name,
+            None => {
+                return Err(CodegenError::new(&format!(
+                    "unable to store a key of type {} with a value of type {} in a map",
+                    arg_types[1], arg_types[2]
+                )))
+            }
+        };
+        let result = context.allocate(Type::None);
+        context.add_instruction(LLVMInstruction::BuildCall {
+            name,
+            args: vec![object.index, key.index, value.index],
+            target: result.index,
+        });
+    }
+    Ok(object)
+}
+
 fn gen_expr(context: &mut Context, args: &[Token]) -> CodegenResult<Object> {
     if let Some((func_token, args)) = args.split_first() {
         match func_token {
@@ -239,7 +315,7 @@ fn compile_expr<'a, 'b, 'c>(
         if let Some(function) = function_by_arg_count.get(&argument_types) {
             let object = context.allocate(function.return_type.clone());
             context.add_instruction(LLVMInstruction::BuildCall {
-                name: func_name.to_owned(),
+                name: function_symbol(func_name, &argument_types),
                 args: argument_objects,
                 target: object.index,
             });
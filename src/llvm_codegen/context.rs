@@ -44,6 +44,22 @@ impl<'a, 'b> Context<'a, 'b> {
         self.function.allocate_object()
     }
 
+    /// allocate a variable of the type on the stack. The alloca is
+    /// added to the start of the entry block, so it runs once per
+    /// call even if the variable is declared in a loop.
+    pub fn allocate_variable(&mut self, object_type: Type) -> Object {
+        let llvm_type = self.compiler.llvm.types.get(&object_type);
+        let object = self.allocate(object_type);
+        self.function.basic_blocks[0].instructions.insert(
+            0,
+            LLVMInstruction::BuildAlloca {
+                llvm_type: llvm_type,
+                target: object.index,
+            },
+        );
+        object
+    }
+
     pub fn const_int(&mut self, value: i64) -> Object {
         let object = self.allocate(Type::Int);
         self.add_instruction(LLVMInstruction::ConstInt {
@@ -53,12 +69,21 @@ impl<'a, 'b> Context<'a, 'b> {
         object
     }
 
+    pub fn const_float(&mut self, value: f64) -> Object {
+        let object = self.allocate(Type::Float);
+        self.add_instruction(LLVMInstruction::ConstFloat {
+            value: value,
+            target: object.index,
+        });
+        object
+    }
+
     /// LLVM GetElementPtr calls must use i32 values to
     /// specify indices. Thus exposing that option.
     /// const_int should be used when authoring code for
     /// disp itself.
     pub fn const_i32(&mut self, value: i32) -> Object {
-        let object = self.allocate(Type::Int);
+        let object = self.allocate(Type::I32);
         self.add_instruction(LLVMInstruction::ConstI32 {
             value: value,
             target: object.index,
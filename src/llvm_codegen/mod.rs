@@ -7,7 +7,7 @@ mod llvm_context;
 mod scope;
 mod types;
 mod utils;
-pub use self::array::create_array;
+pub use self::array::{copy_returned_array, create_array};
 pub use self::compiler::build_functions;
 pub use self::context::Context;
 pub use self::core::{BasicBlock, Function, FunctionType, NativeFunction, Object};
@@ -17,8 +17,8 @@ pub use self::scope::Scope;
 pub use self::types::LLVMTypeCache;
 pub use self::utils::*;
 use super::{
-    get_builtin_expressions, AnnotatedFunction, AnnotatedFunctionMap, CompilerData,
-    LLVMInstruction, Token, Type,
+    get_builtin_expressions, map_address, AnnotatedFunction, AnnotatedFunctionMap, CompilerData,
+    LLVMInstruction, Map, Token, Type,
 };
 
 // the dispcompiler object is a global
@@ -30,10 +30,18 @@ impl LLVMTypeCache {
                 &Type::Array(ref subtype) => LLVMPointerType(self.llvm_declare_array(subtype), 0),
                 &Type::Bool => LLVMInt1TypeInContext(self.context),
                 &Type::Byte => LLVMInt8TypeInContext(self.context),
+                &Type::Float => LLVMDoubleTypeInContext(self.context),
                 &Type::FunctionPrototype => LLVMVoidTypeInContext(self.context),
-                &Type::Int => LLVMInt32TypeInContext(self.context),
+                &Type::Int => LLVMInt64TypeInContext(self.context),
+                &Type::I8 | &Type::U8 => LLVMInt8TypeInContext(self.context),
+                &Type::I16 => LLVMInt16TypeInContext(self.context),
+                &Type::I32 => LLVMInt32TypeInContext(self.context),
+                &Type::U64 => LLVMInt64TypeInContext(self.context),
                 &Type::None => LLVMVoidTypeInContext(self.context),
                 &Type::String => LLVMPointerType(LLVMInt8TypeInContext(self.context), 0),
+                // tokens are opaque pointers, only
+                // manipulated by native functions.
+                &Type::Token => LLVMPointerType(LLVMInt8TypeInContext(self.context), 0),
                 &Type::Map(ref _k, ref _v) => {
                     LLVMPointerType(LLVMVoidTypeInContext(self.context), 0)
                 }
@@ -49,7 +57,7 @@ impl LLVMTypeCache {
         unsafe {
             let mut types = [
                 LLVMPointerType(self.to_llvm_type(base_type), 0),
-                LLVMInt32TypeInContext(self.context),
+                LLVMInt64TypeInContext(self.context),
             ];
             let struct_ref =
                 LLVMStructCreateNamed(self.context, to_ptr(&format!("Array<{:?}>", base_type)));
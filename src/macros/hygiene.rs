@@ -0,0 +1,106 @@
+/// Hygiene for macro expansion.
+///
+/// Every symbol introduced by a macro body (i.e. not passed in as
+/// an argument) is marked with the id of the expansion that introduced
+/// it. After a function has been fully expanded, marked symbols that are
+/// bound by that expansion keep their mark, which makes them unique.
+/// All other marked symbols are references to something defined outside
+/// of the macro (builtins, functions, other macros), and are restored to
+/// their original name.
+use super::Token;
+use std::collections::HashSet;
+
+/// the separator between the original name of a symbol and
+/// the expansion that introduced it. This is not a valid
+/// character in a symbol, so marked symbols can never clash
+/// with names written in source.
+const MARK_SEPARATOR: char = '#';
+
+/// return the symbol name, marked with the expansion id.
+pub fn mark(name: &str, expansion_id: usize) -> String {
+    format!("{}{}{}", unmark(name), MARK_SEPARATOR, expansion_id)
+}
+
+/// return the name of the symbol as written in the source.
+pub fn unmark(name: &str) -> &str {
+    match name.find(MARK_SEPARATOR) {
+        Some(index) => &name[..index],
+        None => name,
+    }
+}
+
+/// return the id of the expansion that introduced
+/// the symbol, if any.
+pub fn expansion_id(name: &str) -> Option<usize> {
+    match name.find(MARK_SEPARATOR) {
+        Some(index) => name[index + 1..].parse().ok(),
+        None => None,
+    }
+}
+
+pub fn is_marked(name: &str) -> bool {
+    name.contains(MARK_SEPARATOR)
+}
+
+/// collect all marked symbols that are bound by a let
+/// expression within the token.
+pub fn collect_bindings(token: &Token, bindings: &mut HashSet<String>) {
+    match token {
+        Token::Expression(ref expression) => {
+            if let (Some(&Token::Symbol(ref head)), Some(&Token::Symbol(ref name))) =
+                (expression.get(0), expression.get(1))
+            {
+                if unmark(head) == "let" && is_marked(name) {
+                    bindings.insert((**name).clone());
+                }
+            }
+            for t in expression {
+                collect_bindings(t, bindings);
+            }
+        }
+        Token::List(ref tokens) | Token::Block(ref tokens) => {
+            for t in tokens {
+                collect_bindings(t, bindings);
+            }
+        }
+        Token::Map(ref map) => {
+            for value in map.values() {
+                collect_bindings(value, bindings);
+            }
+        }
+        Token::Located(_, ref t) => collect_bindings(t, bindings),
+        _ => {}
+    }
+}
+
+/// remove the marks from every symbol that is not in
+/// the set of bindings.
+pub fn resolve_marks(token: &Token, bindings: &HashSet<String>) -> Token {
+    match token {
+        Token::Symbol(ref s) => {
+            if is_marked(s) && !bindings.contains(&**s) {
+                Token::Symbol(Box::new(unmark(s).to_owned()))
+            } else {
+                token.clone()
+            }
+        }
+        Token::Expression(ref tokens) => Token::Expression(resolve_all(tokens, bindings)),
+        Token::List(ref tokens) => Token::List(resolve_all(tokens, bindings)),
+        Token::Block(ref tokens) => Token::Block(resolve_all(tokens, bindings)),
+        Token::Map(ref map) => {
+            let mut result_map = map.clone();
+            for value in result_map.values_mut() {
+                *value = resolve_marks(value, bindings);
+            }
+            Token::Map(result_map)
+        }
+        Token::Located(ref location, ref t) => {
+            Token::Located(location.clone(), Box::new(resolve_marks(t, bindings)))
+        }
+        t => t.clone(),
+    }
+}
+
+fn resolve_all(tokens: &[Token], bindings: &HashSet<String>) -> Vec<Token> {
+    tokens.iter().map(|t| resolve_marks(t, bindings)).collect()
+}
@@ -1,56 +1,305 @@
+mod hygiene;
+mod module;
 mod parser;
+mod pattern;
+mod procedural;
+mod reflection;
+#[cfg(test)]
+mod tests;
 
+pub use self::hygiene::unmark;
+use self::hygiene::{collect_bindings, mark, resolve_marks};
+pub use self::module::Module;
 pub use self::parser::parse_macro;
-use super::{DispError, DispResult, FunctionMap, Token, UnparsedFunction};
-use std::{collections::HashMap, rc::Rc};
+pub use self::procedural::own_token;
+use self::pattern::{expand_pattern_macro, Clause};
+use self::procedural::expand_procedural_macro;
+use super::{
+    annotate_types, build_functions, get_builtin_expressions, runtime_error, token_to_ptr,
+    AnnotatedFunctionMap, Builder, Compiler, DispError, DispResult, Expansion, FunctionMap,
+    GenericResult, LLVMFunction, Location, Map, Token, Type, UnparsedFunction,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    mem::replace,
+    rc::Rc,
+};
 
 /// Macros represent functions that execute
 /// compile time and return back additional syntax blocks to
 /// be evaluated.
 #[derive(Clone, Debug)]
 pub struct Macro {
+    pub kind: MacroKind,
     pub arguments: Vec<String>,
+    /// the parameter that collects any arguments
+    /// beyond the positional ones, as a list.
+    pub rest: Option<String>,
     pub body: Token,
+    /// where the macro was declared, if known.
+    pub location: Option<Location>,
+    /// the module the macro was declared in.
+    pub module: String,
+}
+
+impl Macro {
+    /// the names of all parameters, including
+    /// the rest parameter.
+    pub fn parameters(&self) -> Vec<String> {
+        let mut parameters = self.arguments.clone();
+        if let Some(ref rest) = self.rest {
+            parameters.push(rest.clone());
+        }
+        parameters
+    }
+
+    /// match the arguments passed to the macro to its
+    /// parameters, returning a value for every parameter.
+    pub fn bind_arguments(&self, name: &str, args: &[Token]) -> DispResult<Vec<Token>> {
+        let count = self.arguments.len();
+        let valid = match self.rest {
+            Some(_) => args.len() >= count,
+            None => args.len() == count,
+        };
+        if !valid {
+            return Err(DispError::new(&format!(
+                "expected {}{} arguments for macro {}. found {:?}",
+                if self.rest.is_some() { "at least " } else { "" },
+                count,
+                name,
+                args
+            )));
+        }
+        let mut values = args[..count].to_vec();
+        if self.rest.is_some() {
+            values.push(Token::List(args[count..].to_vec()));
+        }
+        Ok(values)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MacroKind {
+    /// declared with macro!. The body is a template, which
+    /// is returned with the arguments substituted in.
+    Template,
+    /// declared with proc-macro!. The body is executed
+    /// on compile time, and returns the resulting token.
+    Procedural,
+    /// declared with macro! name rules [literals]. The body
+    /// is a list of clauses, matched against the arguments.
+    Pattern {
+        literals: Vec<String>,
+        clauses: Vec<Clause>,
+    },
 }
 
 pub type MacroMap = HashMap<String, Macro>;
 
+pub const DEFAULT_MAX_EXPANSION_DEPTH: usize = 128;
+
+/// the number of macros shown in the chain of an
+/// expansion that is too deep.
+const MAX_CHAIN_LENGTH: usize = 10;
+
+/// the symbol that repeats the template that follows it:
+/// & (print value)
+const REPEAT_SYMBOL: &'static str = "&";
+
+/// the value bound to a macro parameter.
+#[derive(Clone, Debug)]
+pub enum Binding {
+    Token(Token),
+    /// the values bound to a parameter that repeats,
+    /// such as the rest parameter.
+    Repeated(Vec<Binding>),
+}
+
+impl Binding {
+    /// repeated values are substituted as a list.
+    pub fn to_token(&self) -> Token {
+        match self {
+            Binding::Token(ref token) => token.clone(),
+            Binding::Repeated(ref values) => {
+                Token::List(values.iter().map(|v| v.to_token()).collect())
+            }
+        }
+    }
+}
+
+pub type Bindings = HashMap<String, Binding>;
+
+/// The state shared by all macro expansions
+/// performed while loading a program.
+pub struct ExpansionContext {
+    /// the macros visible within the module being loaded.
+    pub macros: MacroMap,
+    /// the name of the module being loaded.
+    pub module: String,
+    /// the macros the module being loaded exports.
+    exports: Vec<String>,
+    /// the macros imported from the prelude, which
+    /// can be shadowed.
+    prelude_macros: HashSet<String>,
+    /// the module whose exported macros are
+    /// imported into every other module.
+    pub prelude: Option<String>,
+    /// the modules that have been loaded, in the
+    /// order they finished loading.
+    pub modules: Vec<Module>,
+    /// the functions the module being loaded has declared
+    /// so far, with their macros expanded. Used to answer
+    /// reflection queries.
+    functions: FunctionMap,
+    /// functions declared by the module being loaded,
+    /// whose macros have not been expanded yet.
+    declared_functions: FunctionMap,
+    /// set while expanding the declared functions for
+    /// reflection, so they are only expanded once.
+    reflecting: bool,
+    /// the modules currently being loaded, including
+    /// the ones waiting for an import to load.
+    loading: Vec<String>,
+    /// the module of the macro every expansion was
+    /// performed for, by expansion id.
+    expansion_modules: HashMap<usize, String>,
+    /// the module of the macro being expanded.
+    expanding_module: Option<String>,
+    /// the number of expansions performed so far. Each
+    /// expansion uses it as a unique id to rename the
+    /// identifiers its macro body introduces.
+    expansion_count: usize,
+    /// symbols created explicitly via gensym. These
+    /// are always kept unique.
+    generated_symbols: HashSet<String>,
+    /// procedural macros are compiled on their first
+    /// expansion, and reused afterward. They are
+    /// stored by module and name.
+    compiled_macros: HashMap<(String, String), LLVMFunction>,
+    /// the function being expanded. None while
+    /// expanding top-level forms.
+    current_function: Option<String>,
+    /// when set, every expansion performed is
+    /// added to the steps.
+    pub record_steps: bool,
+    pub steps: Vec<ExpansionStep>,
+    /// the macros whose results are being expanded,
+    /// outermost first.
+    pub expansion_stack: Vec<String>,
+    /// the maximum number of nested expansions. This
+    /// stops macros that expand into themselves.
+    pub max_depth: usize,
+    /// the location of the token being expanded. On
+    /// an error, this is where the error occurred.
+    pub current_location: Option<Location>,
+    /// the expansion being performed. Tokens from the
+    /// macro body are located within this expansion.
+    current_expansion: Option<Rc<Expansion>>,
+}
+
+/// A single macro expansion, recorded
+/// for debugging purposes.
+#[derive(Debug)]
+pub struct ExpansionStep {
+    pub macro_name: String,
+    pub function: Option<String>,
+    pub call: Token,
+    pub result: Token,
+}
+
+impl ExpansionContext {
+    pub fn new(macros: MacroMap) -> ExpansionContext {
+        ExpansionContext {
+            macros,
+            module: String::from("main"),
+            exports: vec![],
+            prelude_macros: HashSet::new(),
+            prelude: None,
+            modules: vec![],
+            functions: FunctionMap::new(),
+            declared_functions: FunctionMap::new(),
+            reflecting: false,
+            loading: vec![],
+            expansion_modules: HashMap::new(),
+            expanding_module: None,
+            expansion_count: 0,
+            generated_symbols: HashSet::new(),
+            compiled_macros: HashMap::new(),
+            current_function: None,
+            record_steps: false,
+            steps: vec![],
+            expansion_stack: vec![],
+            max_depth: DEFAULT_MAX_EXPANSION_DEPTH,
+            current_location: None,
+            current_expansion: None,
+        }
+    }
+
+    fn next_expansion_id(&mut self) -> usize {
+        self.expansion_count += 1;
+        if let Some(ref module) = self.expanding_module {
+            self.expansion_modules
+                .insert(self.expansion_count, module.clone());
+        }
+        self.expansion_count
+    }
+}
+
 /// modify function map in place, using macros to expand.
 pub fn apply_macros_to_function_map(
-    macros: &MacroMap,
+    context: &mut ExpansionContext,
     functions: &mut FunctionMap,
 ) -> DispResult<()> {
-    for value in functions.values_mut() {
+    for (name, value) in functions.iter_mut() {
         if let Some(ref mut function) = Rc::get_mut(value) {
-            apply_macros_to_function(macros, function)?;
+            context.current_function = Some(name.clone());
+            apply_macros_to_function(context, function)?;
         }
     }
+    context.current_function = None;
     Ok(())
 }
 
-fn apply_macros_to_function(macros: &MacroMap, function: &mut UnparsedFunction) -> DispResult<()> {
-    function.body = apply_macros_to_token(macros, &mut function.body)?;
+fn apply_macros_to_function(
+    context: &mut ExpansionContext,
+    function: &mut UnparsedFunction,
+) -> DispResult<()> {
+    let body = apply_macros_to_token(context, &mut function.body)?;
+    // identifiers introduced by macros keep their unique name
+    // only if they are bound within the function.
+    let mut bindings = context.generated_symbols.clone();
+    bindings.extend(function.args.iter().cloned());
+    collect_bindings(&body, &mut bindings);
+    function.body = resolve_marks(&body, &bindings);
     Ok(())
 }
 
-fn apply_macros_to_token(macros: &MacroMap, token: &mut Token) -> DispResult<Token> {
+fn apply_macros_to_token(context: &mut ExpansionContext, token: &mut Token) -> DispResult<Token> {
     // TODO: figure out how return back original expressions without cloning values
     match token {
-        Token::Expression(e) => return expand_expression(macros, e.clone()),
+        Token::Expression(e) => return expand_expression(context, e.clone()),
+        Token::Located(ref location, ref mut inner) => {
+            // the location is only restored on success, so
+            // errors keep the innermost location.
+            let previous = context.current_location.replace((**location).clone());
+            let result = apply_macros_to_token(context, inner)?;
+            context.current_location = previous;
+            return Ok(Token::Located(location.clone(), Box::new(result)));
+        }
         Token::List(ref mut list) => {
             for i in 0..list.len() {
-                list[i] = apply_macros_to_token(macros, &mut list[i])?;
+                list[i] = apply_macros_to_token(context, &mut list[i])?;
             }
         }
         Token::Block(ref mut block) => {
             for i in 0..block.len() {
-                block[i] = apply_macros_to_token(macros, &mut block[i])?;
+                block[i] = apply_macros_to_token(context, &mut block[i])?;
             }
         }
 
         Token::Map(ref mut m) => {
             for token in m.values_mut() {
-                *token = apply_macros_to_token(macros, &mut *token)?;
+                *token = apply_macros_to_token(context, &mut *token)?;
             }
         }
         _ => {}
@@ -60,76 +309,367 @@ fn apply_macros_to_token(macros: &MacroMap, token: &mut Token) -> DispResult<Tok
 
 /// Return the expanded token if an expansion was performed. Otherwise
 /// return None
-fn expand_expression(macros: &MacroMap, mut expression: Vec<Token>) -> DispResult<Token> {
+fn expand_expression(
+    context: &mut ExpansionContext,
+    mut expression: Vec<Token>,
+) -> DispResult<Token> {
     let expression_length = expression.len();
     for i in 0..expression_length {
-        expression[i] = apply_macros_to_token(macros, &mut expression[i])?;
-    }
-    if let Some((func_token, args)) = expression.split_first() {
-        if let Token::Symbol(ref s) = func_token {
-            if let Some(macro_instance) = macros.get(&**s) {
-                let mut result = expand_macro(macro_instance, args)?;
-                // we then expand the result again, in case
-                // the result is another macro to expand
-                return apply_macros_to_token(macros, &mut result);
-            }
+        expression[i] = apply_macros_to_token(context, &mut expression[i])?;
+    }
+    if let Some(mut result) = expand_macro_call(context, &expression)? {
+        // we then expand the result again, in case
+        // the result is another macro to expand
+        if let Token::Symbol(ref s) = expression[0] {
+            context.expansion_stack.push(unmark(s).to_owned());
         }
+        let result = apply_macros_to_token(context, &mut result);
+        context.expansion_stack.pop();
+        return result;
     }
     Ok(Token::Expression(expression))
 }
 
-pub fn expand_macro(disp_macro: &Macro, args: &[Token]) -> DispResult<Token> {
-    let mut replacement_tokens = HashMap::new();
-    if disp_macro.arguments.len() != args.len() {
-        return Err(DispError::new(&format!(
-            "expected {} arguments for macro. found {:?}",
-            disp_macro.arguments.len(),
-            args
-        )));
+/// If the expression is a macro call, return the result of
+/// expanding it once. Otherwise return None.
+pub fn expand_macro_call(
+    context: &mut ExpansionContext,
+    expression: &[Token],
+) -> DispResult<Option<Token>> {
+    if let Some((&Token::Symbol(ref s), args)) = expression.split_first() {
+        // the symbol may have been introduced by another
+        // macro, so the original name is used for the lookup.
+        let name = unmark(s);
+        let macro_instance = match context.find_macro(s) {
+            Some(m) => m.clone(),
+            None => return Ok(None),
+        };
+        if context.expansion_stack.len() >= context.max_depth {
+            return Err(DispError::new(&format!(
+                "macro expansion exceeded the maximum depth of {}. expansion chain: {}",
+                context.max_depth,
+                format_chain(&context.expansion_stack, name)
+            )));
+        }
+        let expansion = context.current_location.clone().map(|call_site| {
+            Rc::new(Expansion {
+                macro_name: name.to_owned(),
+                call_site,
+            })
+        });
+        let previous_expansion = replace(&mut context.current_expansion, expansion.clone());
+        let previous_module = replace(
+            &mut context.expanding_module,
+            Some(macro_instance.module.clone()),
+        );
+        let result = match macro_instance.kind {
+            MacroKind::Pattern {
+                ref literals,
+                ref clauses,
+            } => expand_pattern_macro(context, name, literals, clauses, args)?,
+            MacroKind::Template => expand_macro(context, name, &macro_instance, args)?,
+            MacroKind::Procedural => expand_procedural_macro(context, name, &macro_instance, args)?,
+        };
+        context.current_expansion = previous_expansion;
+        context.expanding_module = previous_module;
+        // the result is located at the macro declaration. Tokens
+        // from the macro body are more precisely located within it.
+        let result = match macro_instance.location {
+            Some(ref location) => locate(
+                Location {
+                    expansion,
+                    ..location.clone()
+                },
+                result,
+            ),
+            None => result,
+        };
+        if context.record_steps {
+            context.steps.push(ExpansionStep {
+                macro_name: name.to_owned(),
+                function: context.current_function.clone(),
+                call: Token::Expression(expression.to_vec()),
+                result: result.clone(),
+            });
+        }
+        return Ok(Some(result));
     }
-    for i in 0..disp_macro.arguments.len() {
-        replacement_tokens.insert(disp_macro.arguments[i].clone(), args[i].clone());
+    Ok(None)
+}
+
+/// format the chain of macros being expanded, collapsing
+/// repeated expansions of the same macro.
+fn format_chain(stack: &[String], name: &str) -> String {
+    let mut groups: Vec<(&str, usize)> = vec![];
+    for n in stack.iter().map(|s| s.as_str()).chain(Some(name)) {
+        match groups.last_mut() {
+            Some(ref mut group) if group.0 == n => {
+                group.1 += 1;
+                continue;
+            }
+            _ => {}
+        }
+        groups.push((n, 1));
     }
-    let result = expand_token(&replacement_tokens, &disp_macro.body);
-    Ok(result)
+    let mut formatted: Vec<String> = groups
+        .iter()
+        .map(|&(n, count)| match count {
+            1 => n.to_owned(),
+            _ => format!("{} (x{})", n, count),
+        })
+        .collect();
+    if formatted.len() > MAX_CHAIN_LENGTH {
+        let skipped = formatted.len() - MAX_CHAIN_LENGTH;
+        formatted.drain(..skipped);
+        formatted.insert(0, String::from("..."));
+    }
+    formatted.join(" -> ")
+}
+
+pub fn expand_macro(
+    context: &mut ExpansionContext,
+    name: &str,
+    disp_macro: &Macro,
+    args: &[Token],
+) -> DispResult<Token> {
+    let mut bindings = Bindings::new();
+    let values = disp_macro.bind_arguments(name, args)?;
+    for (parameter, value) in disp_macro.parameters().into_iter().zip(values) {
+        let binding = match value {
+            // the rest parameter can be repeated over.
+            Token::List(ref elements) if Some(&parameter) == disp_macro.rest.as_ref() => {
+                Binding::Repeated(elements.iter().cloned().map(Binding::Token).collect())
+            }
+            value => Binding::Token(value),
+        };
+        bindings.insert(parameter, binding);
+    }
+    expand_template(context, &bindings, &disp_macro.body)
+}
+
+/// expand the template with the bound values,
+/// as a new expansion.
+pub fn expand_template(
+    context: &mut ExpansionContext,
+    bindings: &Bindings,
+    template: &Token,
+) -> DispResult<Token> {
+    let expansion_id = context.next_expansion_id();
+    expand_token(context, expansion_id, bindings, template)
 }
 
 // given a token, substitute any values
 // in the macro with the appropriate values.
-pub fn expand_token(replacement_tokens: &HashMap<String, Token>, token: &Token) -> Token {
-    match token {
-        &Token::Symbol(ref s) => match replacement_tokens.get(&(*s.clone())) {
-            Some(t) => t.clone(),
-            None => Token::Symbol(Box::new(*s.clone())),
+// Symbols that are not arguments are marked with the
+// expansion id, to keep them from clashing with
+// symbols at the call site.
+pub fn expand_token(
+    context: &mut ExpansionContext,
+    expansion_id: usize,
+    bindings: &Bindings,
+    token: &Token,
+) -> DispResult<Token> {
+    Ok(match token {
+        &Token::Symbol(ref s) => match bindings.get(&(*s.clone())) {
+            Some(binding) => binding.to_token(),
+            None => Token::Symbol(Box::new(mark(s, expansion_id))),
         },
         &Token::List(ref token_list) => {
-            let mut new_list = vec![];
-            for t in token_list {
-                new_list.push(expand_token(replacement_tokens, t));
-            }
-            Token::List(new_list)
+            Token::List(expand_tokens(context, expansion_id, bindings, token_list)?)
         }
         &Token::Block(ref token_list) => {
-            let mut new_list = vec![];
-            for t in token_list {
-                new_list.push(expand_token(replacement_tokens, t));
-            }
-            Token::Block(new_list)
+            Token::Block(expand_tokens(context, expansion_id, bindings, token_list)?)
         }
         &Token::Expression(ref token_list) => {
-            let mut new_list = vec![];
-            for t in token_list {
-                new_list.push(expand_token(replacement_tokens, t));
+            if let Some(result) = expand_special_form(context, expansion_id, bindings, token_list) {
+                return Ok(result);
             }
-            Token::Expression(new_list)
+            Token::Expression(expand_tokens(context, expansion_id, bindings, token_list)?)
+        }
+        &Token::Located(ref location, ref t) => {
+            let result = expand_token(context, expansion_id, bindings, t)?;
+            locate(
+                Location {
+                    expansion: context.current_expansion.clone(),
+                    ..(**location).clone()
+                },
+                result,
+            )
         }
         &Token::Map(ref map) => {
-            let mut result_map = HashMap::new();
+            let mut result_map = Map::new();
             for (key, value) in map.iter() {
-                result_map.insert(key.clone(), expand_token(replacement_tokens, value));
+                result_map.insert(
+                    key.clone(),
+                    expand_token(context, expansion_id, bindings, value)?,
+                );
             }
             Token::Map(Box::new(result_map))
         }
         t => t.clone(),
+    })
+}
+
+/// expand every token in the list. Two forms are
+/// replaced by multiple elements:
+/// * (splice name): the elements of the list bound to
+///   name, such as the rest parameter.
+/// * & subtemplate: the subtemplate, expanded once for
+///   every value of the repeated parameters it contains.
+fn expand_tokens(
+    context: &mut ExpansionContext,
+    expansion_id: usize,
+    bindings: &Bindings,
+    tokens: &[Token],
+) -> DispResult<Vec<Token>> {
+    let mut result = vec![];
+    let mut tokens = tokens.iter();
+    while let Some(t) = tokens.next() {
+        match t.unlocated() {
+            Token::Symbol(ref s) if unmark(s) == REPEAT_SYMBOL => match tokens.next() {
+                Some(subtemplate) => {
+                    for repetition in repeat_bindings(bindings, subtemplate)? {
+                        result.push(expand_token(
+                            context,
+                            expansion_id,
+                            &repetition,
+                            subtemplate,
+                        )?);
+                    }
+                }
+                None => {
+                    return Err(DispError::new(&format!(
+                        "expected a template after {}",
+                        REPEAT_SYMBOL
+                    )))
+                }
+            },
+            Token::Expression(ref e) if is_splice(e) => {
+                match expand_token(context, expansion_id, bindings, &e[1])? {
+                    Token::List(elements)
+                    | Token::Block(elements)
+                    | Token::Expression(elements) => result.extend(elements),
+                    element => result.push(element),
+                }
+            }
+            _ => result.push(expand_token(context, expansion_id, bindings, t)?),
+        }
+    }
+    Ok(result)
+}
+
+/// annotate the result of an expansion with its location.
+/// Only expressions and blocks are located, so symbols and
+/// literals can still be used wherever the compiler
+/// expects them. Tokens that are already located keep
+/// their more precise location.
+fn locate(location: Location, token: Token) -> Token {
+    match token {
+        Token::Expression(_) | Token::Block(_) => {
+            Token::Located(Box::new(location), Box::new(token))
+        }
+        t => t,
+    }
+}
+
+fn is_splice(expression: &[Token]) -> bool {
+    match expression {
+        [Token::Symbol(ref form), _] => unmark(form) == "splice",
+        _ => false,
+    }
+}
+
+/// return the bindings for every repetition of the subtemplate.
+/// The subtemplate is repeated once for each value of the
+/// repeated parameters it references, which must all have
+/// the same number of values.
+fn repeat_bindings(bindings: &Bindings, subtemplate: &Token) -> DispResult<Vec<Bindings>> {
+    let mut symbols = HashSet::new();
+    collect_symbols(subtemplate, &mut symbols);
+    let mut repeated = vec![];
+    for symbol in symbols {
+        if let Some(&Binding::Repeated(ref values)) = bindings.get(&symbol) {
+            repeated.push((symbol, values));
+        }
+    }
+    let count = match repeated.first() {
+        Some((_, values)) => values.len(),
+        None => {
+            return Err(DispError::new(&format!(
+                "the repeated template {} does not reference a repeated parameter",
+                subtemplate
+            )))
+        }
+    };
+    let mut result = vec![];
+    for i in 0..count {
+        let mut repetition = bindings.clone();
+        for (symbol, values) in &repeated {
+            match values.get(i) {
+                Some(value) => repetition.insert(symbol.clone(), value.clone()),
+                None => {
+                    return Err(DispError::new(&format!(
+                        "repeated parameters in template {} have a different number of values",
+                        subtemplate
+                    )))
+                }
+            };
+        }
+        result.push(repetition);
+    }
+    Ok(result)
+}
+
+fn collect_symbols(token: &Token, symbols: &mut HashSet<String>) {
+    match token {
+        Token::Symbol(ref s) => {
+            symbols.insert((**s).clone());
+        }
+        Token::Expression(ref tokens) | Token::List(ref tokens) | Token::Block(ref tokens) => {
+            for t in tokens {
+                collect_symbols(t, symbols);
+            }
+        }
+        Token::Map(ref map) => {
+            for value in map.values() {
+                collect_symbols(value, symbols);
+            }
+        }
+        Token::Located(_, ref t) => collect_symbols(t, symbols),
+        _ => {}
+    }
+}
+
+/// Macro bodies support a couple of forms to control hygiene:
+/// * (capture name): refer to name as it is at the call site,
+///   rather than introducing a new identifier.
+/// * (gensym name): return a new symbol that is unique
+///   within the program. Within a single expansion, the same
+///   name will return the same symbol.
+fn expand_special_form(
+    context: &mut ExpansionContext,
+    expansion_id: usize,
+    bindings: &Bindings,
+    expression: &[Token],
+) -> Option<Token> {
+    if expression.len() != 2 {
+        return None;
+    }
+    if let (&Token::Symbol(ref form), &Token::Symbol(ref name)) = (&expression[0], &expression[1]) {
+        match unmark(form) {
+            "capture" => {
+                return Some(match bindings.get(&**name) {
+                    Some(binding) => binding.to_token(),
+                    None => Token::Symbol(name.clone()),
+                })
+            }
+            "gensym" => {
+                let symbol = mark(name, expansion_id);
+                context.generated_symbols.insert(symbol.clone());
+                return Some(Token::Symbol(Box::new(symbol)));
+            }
+            _ => {}
+        }
     }
+    None
 }
@@ -0,0 +1,221 @@
+/// Macro modules.
+///
+/// Every file is a module, with its own set of macros: the ones it
+/// declares, and the ones it imports from other modules. Macros are
+/// exported with export!, and imported with import!:
+///
+/// export! for for-range
+/// import! lib [for]
+///
+/// The macros exported by the prelude are imported into every
+/// other module. Unlike other imports, a declaration or import with
+/// the same name shadows them.
+///
+/// Symbols introduced by a macro body refer to the macros visible
+/// in the module the macro was declared in, so a macro can expand
+/// into macros that the calling module has not imported.
+use super::hygiene::expansion_id;
+use super::{DispError, DispResult, ExpansionContext, FunctionMap, Macro, MacroMap};
+use std::collections::HashSet;
+use std::mem::replace;
+
+/// A module that has been loaded.
+pub struct Module {
+    pub name: String,
+    /// all macros visible within the module.
+    pub macros: MacroMap,
+    pub exports: HashSet<String>,
+    pub functions: FunctionMap,
+}
+
+/// The state of a module that is being loaded.
+pub struct ModuleState {
+    name: String,
+    macros: MacroMap,
+    exports: Vec<String>,
+    prelude_macros: HashSet<String>,
+    functions: FunctionMap,
+    declared_functions: FunctionMap,
+}
+
+impl ExpansionContext {
+    /// start loading a module. The state of the module that was
+    /// being loaded is returned, to be restored once it finishes.
+    pub fn begin_module(&mut self, name: &str) -> DispResult<ModuleState> {
+        if self.loading.iter().any(|m| m == name) {
+            return Err(DispError::new(&format!(
+                "module {} is imported cyclically: {} -> {}",
+                name,
+                self.loading.join(" -> "),
+                name
+            )));
+        }
+        self.loading.push(name.to_owned());
+        Ok(ModuleState {
+            name: replace(&mut self.module, name.to_owned()),
+            macros: replace(&mut self.macros, MacroMap::new()),
+            exports: replace(&mut self.exports, vec![]),
+            prelude_macros: replace(&mut self.prelude_macros, HashSet::new()),
+            functions: replace(&mut self.functions, FunctionMap::new()),
+            declared_functions: replace(&mut self.declared_functions, FunctionMap::new()),
+        })
+    }
+
+    /// finish loading the current module, and restore
+    /// the module that was being loaded before.
+    pub fn finish_module(
+        &mut self,
+        previous: ModuleState,
+        functions: FunctionMap,
+    ) -> DispResult<()> {
+        for export in &self.exports {
+            if !self.macros.contains_key(export) {
+                return Err(DispError::new(&format!(
+                    "module {} exports {}, which is not a macro",
+                    self.module, export
+                )));
+            }
+        }
+        self.loading.pop();
+        let module = Module {
+            name: replace(&mut self.module, previous.name),
+            macros: replace(&mut self.macros, previous.macros),
+            exports: replace(&mut self.exports, previous.exports)
+                .into_iter()
+                .collect(),
+            functions,
+        };
+        self.prelude_macros = previous.prelude_macros;
+        self.functions = previous.functions;
+        self.declared_functions = previous.declared_functions;
+        self.modules.push(module);
+        Ok(())
+    }
+
+    pub fn export_macro(&mut self, name: String) {
+        self.exports.push(name);
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.get_module(name).is_some()
+    }
+
+    fn get_module(&self, name: &str) -> Option<&Module> {
+        self.modules.iter().find(|m| m.name == name)
+    }
+
+    /// add a macro declared by the current module.
+    pub fn declare_macro(&mut self, name: String, mut disp_macro: Macro) -> DispResult<()> {
+        if let Some(existing) = self.macros.get(&name) {
+            if !self.prelude_macros.contains(&name) {
+                return Err(DispError::new(&format!(
+                    "unable to declare macro {}: a macro named {} is already {}",
+                    name,
+                    name,
+                    self.describe_origin(existing)
+                )));
+            }
+        }
+        self.prelude_macros.remove(&name);
+        disp_macro.module = self.module.clone();
+        self.macros.insert(name, disp_macro);
+        Ok(())
+    }
+
+    /// import macros exported by a loaded module. If no names
+    /// are passed, all exported macros are imported.
+    pub fn import_macros(&mut self, module: &str, names: Option<Vec<String>>) -> DispResult<()> {
+        for (name, disp_macro) in self.exported_macros(module, names)? {
+            if let Some(existing) = self.macros.get(&name) {
+                // the same macro may be imported via multiple modules.
+                if existing.module != disp_macro.module && !self.prelude_macros.contains(&name) {
+                    return Err(DispError::new(&format!(
+                        "unable to import macro {} from module {}: a macro named {} is already {}",
+                        name,
+                        module,
+                        name,
+                        self.describe_origin(existing)
+                    )));
+                }
+            }
+            self.prelude_macros.remove(&name);
+            self.macros.insert(name, disp_macro);
+        }
+        Ok(())
+    }
+
+    /// import every macro exported by the prelude. These are
+    /// shadowed by any other macro of the same name.
+    pub fn import_prelude(&mut self, module: &str) -> DispResult<()> {
+        for (name, disp_macro) in self.exported_macros(module, None)? {
+            if !self.macros.contains_key(&name) {
+                self.prelude_macros.insert(name.clone());
+                self.macros.insert(name, disp_macro);
+            }
+        }
+        Ok(())
+    }
+
+    fn exported_macros(
+        &self,
+        module: &str,
+        names: Option<Vec<String>>,
+    ) -> DispResult<Vec<(String, Macro)>> {
+        let module = match self.get_module(module) {
+            Some(m) => m,
+            None => {
+                return Err(DispError::new(&format!(
+                    "module {} has not been loaded",
+                    module
+                )))
+            }
+        };
+        let names = names.unwrap_or_else(|| module.exports.iter().cloned().collect());
+        let mut result = vec![];
+        for name in names {
+            if !module.exports.contains(&name) {
+                return Err(DispError::new(&format!(
+                    "module {} does not export macro {}",
+                    module.name, name
+                )));
+            }
+            result.push((name.clone(), module.macros[&name].clone()));
+        }
+        Ok(result)
+    }
+
+    fn describe_origin(&self, disp_macro: &Macro) -> String {
+        if disp_macro.module == self.module {
+            format!("declared in module {}", disp_macro.module)
+        } else {
+            format!("imported from module {}", disp_macro.module)
+        }
+    }
+
+    /// run f with the macros of the module visible,
+    /// rather than the ones of the current module.
+    pub fn with_module_macros<T>(&mut self, module: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        let macros = match self.get_module(module) {
+            Some(m) => m.macros.clone(),
+            None => return f(self),
+        };
+        let previous = replace(&mut self.macros, macros);
+        let result = f(self);
+        self.macros = previous;
+        result
+    }
+
+    /// return the macro the symbol refers to, if any. Symbols
+    /// introduced by a macro refer to the macros of the module
+    /// the macro was declared in.
+    pub fn find_macro(&self, symbol: &str) -> Option<&Macro> {
+        let name = super::unmark(symbol);
+        let module = expansion_id(symbol)
+            .and_then(|id| self.expansion_modules.get(&id))
+            .and_then(|module| self.get_module(module));
+        match module {
+            Some(module) => module.macros.get(name),
+            None => self.macros.get(name),
+        }
+    }
+}
@@ -1,11 +1,33 @@
-use super::{DispError, DispResult, Macro, Token};
+use super::pattern::parse_clauses;
+use super::{unmark, DispError, DispResult, Macro, MacroKind, Token};
+
+/// the symbol that precedes the rest parameter:
+/// [first & rest]
+const REST_SEPARATOR: &'static str = "&";
+
+/// the symbol that declares a pattern macro:
+/// macro! name rules [literals]
+const RULES_SYMBOL: &'static str = "rules";
 
 pub fn parse_macro(args: Vec<Token>) -> DispResult<(String, Macro)> {
+    if args.len() == 5 {
+        return parse_pattern_macro(args);
+    }
     if args.len() == 4 {
+        let kind = match args[0] {
+            Token::BangSymbol(ref s) if **s == "proc-macro" => MacroKind::Procedural,
+            _ => MacroKind::Template,
+        };
         if let (&Token::Symbol(ref name), &Token::List(ref token_list)) = (&args[1], &args[2]) {
             let mut arguments = vec![];
-            for t in token_list {
+            let mut rest = None;
+            let mut tokens = token_list.iter();
+            while let Some(t) = tokens.next() {
                 if let Token::Symbol(ref arg_name) = t {
+                    if unmark(arg_name) == REST_SEPARATOR {
+                        rest = Some(parse_rest(tokens.as_slice())?);
+                        break;
+                    }
                     arguments.push(*arg_name.clone());
                 } else {
                     return Err(DispError::new(&format!(
@@ -14,11 +36,17 @@ pub fn parse_macro(args: Vec<Token>) -> DispResult<(String, Macro)> {
                     )));
                 }
             }
+            // macros declared by other macros are
+            // visible to the whole program.
             return Ok((
-                (**name).clone(),
+                unmark(name).to_owned(),
                 Macro {
+                    kind: kind,
                     arguments: arguments,
+                    rest: rest,
                     body: args[3].clone(),
+                    location: None,
+                    module: String::new(),
                 },
             ));
         }
@@ -28,3 +56,55 @@ pub fn parse_macro(args: Vec<Token>) -> DispResult<(String, Macro)> {
         args
     )))
 }
+
+fn parse_pattern_macro(args: Vec<Token>) -> DispResult<(String, Macro)> {
+    if let (&Token::Symbol(ref name), &Token::Symbol(ref rules), &Token::List(ref token_list)) =
+        (&args[1], &args[2], &args[3])
+    {
+        if unmark(rules) == RULES_SYMBOL {
+            let mut literals = vec![];
+            for t in token_list {
+                match t {
+                    Token::Symbol(ref literal) => literals.push((**literal).clone()),
+                    t => {
+                        return Err(DispError::new(&format!(
+                            "macro literals should be symbols. found {}",
+                            t
+                        )))
+                    }
+                }
+            }
+            let clauses = parse_clauses(&args[4], &literals)?;
+            return Ok((
+                unmark(name).to_owned(),
+                Macro {
+                    kind: MacroKind::Pattern {
+                        literals: literals,
+                        clauses: clauses,
+                    },
+                    arguments: vec![],
+                    rest: None,
+                    body: args[4].clone(),
+                    location: None,
+                    module: String::new(),
+                },
+            ));
+        }
+    }
+    Err(DispError::new(&format!(
+        "a pattern macro requires a name, {}, literals and clauses. found {:?}",
+        RULES_SYMBOL, args
+    )))
+}
+
+/// the rest parameter should be the only symbol
+/// after the separator.
+fn parse_rest(tokens: &[Token]) -> DispResult<String> {
+    match tokens {
+        [Token::Symbol(ref name)] => Ok((**name).clone()),
+        _ => Err(DispError::new(&format!(
+            "expected a single parameter after {}. found {:?}",
+            REST_SEPARATOR, tokens
+        ))),
+    }
+}
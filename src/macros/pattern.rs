@@ -0,0 +1,240 @@
+/// Pattern macros.
+///
+/// A pattern macro is declared with a list of literal symbols,
+/// followed by clauses of a pattern and a template:
+///
+/// macro! for rules [from to]
+///     [symbol from start to end body] (for-range symbol start end body)
+///     [init cond end body] <init (while cond <body end>)>
+///
+/// The template of the first clause whose pattern matches the
+/// arguments is expanded. Within a pattern:
+/// * a literal symbol only matches the same symbol.
+/// * _ matches any token.
+/// * any other symbol matches any token, binding it to the symbol.
+/// * lists, expressions and blocks match tokens of the same shape,
+///   with their elements matched recursively.
+/// * & subpattern matches any remaining elements against the subpattern.
+///   Symbols bound within the subpattern are repeated, and are used in a
+///   template via & subtemplate.
+use super::{
+    expand_template, unmark, Binding, Bindings, DispError, DispResult, ExpansionContext, Token,
+    REPEAT_SYMBOL,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clause {
+    pub pattern: Vec<Token>,
+    pub template: Token,
+}
+
+/// parse the clauses of a pattern macro. Each clause is a line,
+/// starting with the pattern. The rest of the line is the template.
+pub fn parse_clauses(body: &Token, literals: &[String]) -> DispResult<Vec<Clause>> {
+    let lines = match body {
+        Token::Block(ref lines) => lines,
+        t => {
+            return Err(DispError::new(&format!(
+                "expected a block of clauses for a pattern macro. found {}",
+                t
+            )))
+        }
+    };
+    let mut clauses = vec![];
+    for line in lines {
+        match line.unlocated() {
+            Token::Expression(ref tokens) if tokens.len() >= 2 => match tokens[0] {
+                Token::List(ref pattern) => {
+                    check_repeats(&tokens[0])?;
+                    check_variables(&tokens[0], literals)?;
+                    let template = match tokens.len() {
+                        2 => tokens[1].clone(),
+                        _ => Token::Expression(tokens[1..].to_vec()),
+                    };
+                    clauses.push(Clause {
+                        pattern: pattern.clone(),
+                        // the template is located at its clause.
+                        template: match line {
+                            Token::Located(ref location, _) => {
+                                Token::Located(location.clone(), Box::new(template))
+                            }
+                            _ => template,
+                        },
+                    })
+                }
+                ref t => {
+                    return Err(DispError::new(&format!(
+                        "a pattern should be a list. found {}",
+                        t
+                    )))
+                }
+            },
+            // comments
+            Token::None => {}
+            t => {
+                return Err(DispError::new(&format!(
+                    "a clause requires a pattern and a template. found {}",
+                    t
+                )))
+            }
+        }
+    }
+    Ok(clauses)
+}
+
+pub fn expand_pattern_macro(
+    context: &mut ExpansionContext,
+    name: &str,
+    literals: &[String],
+    clauses: &[Clause],
+    args: &[Token],
+) -> DispResult<Token> {
+    for clause in clauses {
+        let mut bindings = Bindings::new();
+        if match_sequence(&clause.pattern, args, literals, &mut bindings) {
+            return expand_template(context, &bindings, &clause.template);
+        }
+    }
+    Err(DispError::new(&format!(
+        "no pattern of macro {} matches the arguments {:?}",
+        name, args
+    )))
+}
+
+fn match_sequence(
+    patterns: &[Token],
+    tokens: &[Token],
+    literals: &[String],
+    bindings: &mut Bindings,
+) -> bool {
+    let repeat_index = patterns.iter().position(is_repeat);
+    let (fixed, subpattern) = match repeat_index {
+        Some(i) => (&patterns[..i], patterns.get(i + 1)),
+        None => (patterns, None),
+    };
+    let matches_length = match subpattern {
+        Some(_) => tokens.len() >= fixed.len(),
+        None => tokens.len() == fixed.len(),
+    };
+    if !matches_length {
+        return false;
+    }
+    for (pattern, token) in fixed.iter().zip(tokens) {
+        if !match_pattern(pattern, token, literals, bindings) {
+            return false;
+        }
+    }
+    if let Some(subpattern) = subpattern {
+        let mut repetitions = vec![];
+        for token in &tokens[fixed.len()..] {
+            let mut repetition = Bindings::new();
+            if !match_pattern(subpattern, token, literals, &mut repetition) {
+                return false;
+            }
+            repetitions.push(repetition);
+        }
+        let mut variables = vec![];
+        pattern_variables(subpattern, literals, &mut variables);
+        for variable in variables {
+            let values = repetitions
+                .iter_mut()
+                .map(|r| r.remove(&variable).unwrap())
+                .collect();
+            bindings.insert(variable, Binding::Repeated(values));
+        }
+    }
+    true
+}
+
+fn match_pattern(
+    pattern: &Token,
+    token: &Token,
+    literals: &[String],
+    bindings: &mut Bindings,
+) -> bool {
+    match (pattern.unlocated(), token.unlocated()) {
+        (Token::Symbol(ref p), _) if **p == "_" => true,
+        (Token::Symbol(ref p), _) if is_literal(p, literals) => match token.unlocated() {
+            Token::Symbol(ref s) => unmark(s) == unmark(p),
+            _ => false,
+        },
+        (Token::Symbol(ref p), _) => {
+            bindings.insert((**p).clone(), Binding::Token(token.clone()));
+            true
+        }
+        (Token::List(ref p), Token::List(ref t))
+        | (Token::Expression(ref p), Token::Expression(ref t))
+        | (Token::Block(ref p), Token::Block(ref t)) => match_sequence(p, t, literals, bindings),
+        (Token::Map(_), _) => false,
+        (p, t) => p == t,
+    }
+}
+
+fn is_literal(symbol: &str, literals: &[String]) -> bool {
+    literals.iter().any(|l| unmark(l) == unmark(symbol))
+}
+
+/// a symbol may only be bound once within a pattern.
+fn is_repeat(pattern: &Token) -> bool {
+    match pattern.unlocated() {
+        Token::Symbol(ref s) => unmark(s) == REPEAT_SYMBOL,
+        _ => false,
+    }
+}
+
+/// & should be followed by a single subpattern,
+/// which ends the sequence.
+fn check_repeats(pattern: &Token) -> DispResult<()> {
+    match pattern.unlocated() {
+        Token::List(ref patterns)
+        | Token::Expression(ref patterns)
+        | Token::Block(ref patterns) => {
+            if let Some(i) = patterns.iter().position(is_repeat) {
+                if patterns.len() != i + 2 || is_repeat(&patterns[i + 1]) {
+                    return Err(DispError::new(&format!(
+                        "expected a single subpattern after {} in the pattern {}",
+                        REPEAT_SYMBOL, pattern
+                    )));
+                }
+            }
+            for p in patterns {
+                check_repeats(p)?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_variables(pattern: &Token, literals: &[String]) -> DispResult<()> {
+    let mut variables = vec![];
+    pattern_variables(pattern, literals, &mut variables);
+    for (i, variable) in variables.iter().enumerate() {
+        if variables[..i].contains(variable) {
+            return Err(DispError::new(&format!(
+                "the pattern {} binds {} more than once",
+                pattern, variable
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// return all the symbols the pattern binds.
+fn pattern_variables(pattern: &Token, literals: &[String], variables: &mut Vec<String>) {
+    match pattern.unlocated() {
+        Token::Symbol(ref s) => {
+            if **s != "_" && unmark(s) != REPEAT_SYMBOL && !is_literal(s, literals) {
+                variables.push((**s).clone());
+            }
+        }
+        Token::List(ref patterns)
+        | Token::Expression(ref patterns)
+        | Token::Block(ref patterns) => {
+            for p in patterns {
+                pattern_variables(p, literals, variables);
+            }
+        }
+        _ => {}
+    }
+}
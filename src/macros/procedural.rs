@@ -0,0 +1,185 @@
+/// Procedural macros.
+///
+/// A procedural macro body is regular disp code, which is
+/// compiled and executed during macro expansion. The arguments
+/// are passed in as tokens, and the token the body returns is
+/// the result of the expansion.
+///
+/// The body is compiled once per macro, into a function named
+/// "macro-expander". It is invoked by a main function that reads
+/// the arguments and stores the result via native calls, which
+/// exchange tokens with the compiler through a thread local frame.
+use super::hygiene::mark;
+use super::{
+    annotate_types, apply_macros_to_function, build_functions, runtime_error, token_to_ptr,
+    Builder, Compiler, DispError, DispResult, ExpansionContext, FunctionMap, GenericResult,
+    LLVMFunction, Macro, Token, UnparsedFunction,
+};
+use libc::c_char;
+use std::{
+    cell::RefCell,
+    collections::{HashSet, VecDeque},
+    ffi::CStr,
+    rc::Rc,
+};
+
+const EXPANDER_NAME: &'static str = "macro-expander";
+
+/// The state of the procedural macro currently
+/// being executed.
+pub struct MacroFrame {
+    arguments: VecDeque<Token>,
+    result: Option<Token>,
+    expansion_id: usize,
+    generated_symbols: HashSet<String>,
+    /// the functions visible to reflection queries.
+    pub functions: FunctionMap,
+    /// set by a builtin that failed. The expansion
+    /// fails with the error once the macro returns.
+    pub error: Option<String>,
+    /// the tokens allocated by the builtins, which
+    /// are freed once the macro returns.
+    tokens: Vec<*mut Token>,
+}
+
+thread_local! {
+    static FRAME: RefCell<Option<MacroFrame>> = RefCell::new(None);
+}
+
+pub fn expand_procedural_macro(
+    context: &mut ExpansionContext,
+    name: &str,
+    disp_macro: &Macro,
+    args: &[Token],
+) -> DispResult<Token> {
+    let values = disp_macro.bind_arguments(name, args)?;
+    let key = (disp_macro.module.clone(), name.to_owned());
+    let function = match context.compiled_macros.get(&key) {
+        Some(f) => *f,
+        None => {
+            // the body uses the macros of the module
+            // the macro was declared in.
+            let module = disp_macro.module.clone();
+            let f = context
+                .with_module_macros(&module, |context| compile_macro(context, name, disp_macro))?;
+            context.compiled_macros.insert(key, f);
+            f
+        }
+    };
+    let functions = context.known_functions();
+    let expansion_id = context.next_expansion_id();
+    FRAME.with(|frame| {
+        *frame.borrow_mut() = Some(MacroFrame {
+            arguments: values.into_iter().collect(),
+            result: None,
+            expansion_id,
+            generated_symbols: HashSet::new(),
+            functions,
+            error: None,
+            tokens: vec![],
+        })
+    });
+    function();
+    let frame = FRAME.with(|frame| frame.borrow_mut().take()).unwrap();
+    for token in frame.tokens {
+        unsafe { drop(Box::from_raw(token)) };
+    }
+    context.generated_symbols.extend(frame.generated_symbols);
+    if let Some(error) = frame.error {
+        return Err(DispError::new(&format!(
+            "procedural macro {} failed: {}",
+            name, error
+        )));
+    }
+    match frame.result {
+        Some(result) => Ok(result),
+        None => Err(DispError::new(&format!(
+            "procedural macro {} did not return a token",
+            name
+        ))),
+    }
+}
+
+fn compile_macro(
+    context: &mut ExpansionContext,
+    name: &str,
+    disp_macro: &Macro,
+) -> DispResult<LLVMFunction> {
+    let mut expander = UnparsedFunction::new(disp_macro.parameters(), disp_macro.body.clone());
+    // macros used within the body are expanded first.
+    apply_macros_to_function(context, &mut expander)?;
+    let mut call = vec![symbol(EXPANDER_NAME)];
+    for _ in disp_macro.parameters() {
+        call.push(Token::Expression(vec![symbol("macro-argument")]));
+    }
+    let main = Token::Expression(vec![symbol("macro-result"), Token::Expression(call)]);
+    let mut functions = FunctionMap::new();
+    functions.insert(String::from(EXPANDER_NAME), Rc::new(expander));
+    functions.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(vec![], Token::Block(vec![main]))),
+    );
+    match build_macro(&functions) {
+        Ok(f) => Ok(f),
+        Err(e) => Err(DispError::new(&format!(
+            "unable to compile procedural macro {}: {}",
+            name, e
+        ))),
+    }
+}
+
+fn build_macro(functions: &FunctionMap) -> GenericResult<LLVMFunction> {
+    let mut compiler = Compiler::new();
+    let annotated_functions = annotate_types(&mut compiler, functions)?;
+    build_functions(&mut compiler, &annotated_functions)?;
+    let mut builder = Builder::new(&compiler.llvm);
+    builder.build(&compiler.data, &mut compiler.llvm.types);
+    Ok(builder.get_function("main")?)
+}
+
+fn symbol(name: &str) -> Token {
+    Token::Symbol(Box::new(String::from(name)))
+}
+
+/// run the function with the frame of the procedural macro being
+/// executed. It is called by native functions, which can not
+/// panic, so calling it outside of a macro is a runtime error.
+pub fn with_frame<T>(f: impl FnOnce(&mut MacroFrame) -> T) -> T {
+    FRAME.with(|frame| match frame.borrow_mut().as_mut() {
+        Some(frame) => f(frame),
+        None => runtime_error("macro builtins can only be called within a procedural macro"),
+    })
+}
+
+/// give the frame of the procedural macro being executed, if any,
+/// ownership of a token allocated by a builtin.
+pub fn own_token(token: *mut Token) {
+    FRAME.with(|frame| {
+        if let Some(frame) = frame.borrow_mut().as_mut() {
+            frame.tokens.push(token);
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn macro_argument() -> *mut Token {
+    let argument = with_frame(|frame| frame.arguments.pop_front().unwrap_or(Token::None));
+    token_to_ptr(argument)
+}
+
+#[no_mangle]
+pub extern "C" fn macro_result(token: *mut Token) {
+    let token = unsafe { (*token).clone() };
+    with_frame(|frame| frame.result = Some(token));
+}
+
+#[no_mangle]
+pub extern "C" fn macro_gensym(name: *const c_char) -> *mut Token {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    let symbol = with_frame(|frame| {
+        let symbol = mark(name, frame.expansion_id);
+        frame.generated_symbols.insert(symbol.clone());
+        symbol
+    });
+    token_to_ptr(Token::Symbol(Box::new(symbol)))
+}
@@ -0,0 +1,214 @@
+/// Compile-time reflection.
+///
+/// Procedural macros can query the program being compiled:
+/// whether a function exists, its arity, its inferred signature,
+/// and the type of an expression. Types are inferred by annotating
+/// a program of the known functions, with a function returning
+/// every expression queried.
+use super::procedural::with_frame;
+use super::{
+    annotate_types, apply_macros_to_function, get_builtin_expressions, unmark,
+    AnnotatedFunctionMap, Compiler, ExpansionContext, FunctionMap, Token, Type, UnparsedFunction,
+};
+use libc::c_char;
+use std::{
+    ffi::{CStr, CString},
+    mem::replace,
+    rc::Rc,
+};
+
+/// the prefix of the functions returning the queried
+/// expressions. It can not be used by a symbol, which
+/// keeps it distinct from the functions of the program.
+const REFLECTED_FUNCTION: &'static str = "#reflected";
+
+impl ExpansionContext {
+    /// record a function declared by the module being loaded. A
+    /// copy is kept, as the function is modified by its expansion.
+    pub fn declare_function(&mut self, name: &str, function: &UnparsedFunction) {
+        self.declared_functions.insert(
+            name.to_owned(),
+            Rc::new(UnparsedFunction::new(
+                function.args.clone(),
+                function.body.clone(),
+            )),
+        );
+    }
+
+    /// all functions declared so far: the ones of the loaded
+    /// modules, and the ones the current module has declared.
+    pub fn known_functions(&mut self) -> FunctionMap {
+        if !self.reflecting {
+            self.reflecting = true;
+            self.expand_declared_functions();
+            self.reflecting = false;
+        }
+        let mut functions = FunctionMap::new();
+        for module in &self.modules {
+            for (name, function) in &module.functions {
+                if name != "main" {
+                    functions.insert(name.clone(), function.clone());
+                }
+            }
+        }
+        for (name, function) in self.functions.iter().chain(&self.declared_functions) {
+            functions.insert(name.clone(), function.clone());
+        }
+        functions
+    }
+
+    /// expand the macros of a copy of every declared function,
+    /// leaving the state of the expansion in progress untouched.
+    fn expand_declared_functions(&mut self) {
+        let record_steps = replace(&mut self.record_steps, false);
+        let expansion_stack = replace(&mut self.expansion_stack, vec![]);
+        let current_function = self.current_function.clone();
+        let current_location = self.current_location.clone();
+        let current_expansion = self.current_expansion.clone();
+        let names: Vec<String> = self.declared_functions.keys().cloned().collect();
+        for name in names {
+            let function = self.declared_functions.remove(&name).unwrap();
+            let mut expanded = UnparsedFunction::new(function.args.clone(), function.body.clone());
+            self.current_function = Some(name.clone());
+            // errors are reported once the function
+            // itself is expanded.
+            let function = match apply_macros_to_function(self, &mut expanded) {
+                Ok(()) => Rc::new(expanded),
+                Err(_) => function,
+            };
+            self.functions.insert(name, function);
+        }
+        self.record_steps = record_steps;
+        self.expansion_stack = expansion_stack;
+        self.current_function = current_function;
+        self.current_location = current_location;
+        self.current_expansion = current_expansion;
+    }
+}
+
+fn to_str<'a>(value: *const c_char) -> &'a str {
+    unsafe { CStr::from_ptr(value).to_str().unwrap() }
+}
+
+fn to_c_string(value: String) -> *const c_char {
+    CString::new(value).unwrap().into_raw()
+}
+
+/// run the query against the functions visible to the macro.
+/// An error is stored in the frame, failing the expansion once
+/// the macro returns, and the default value is returned instead.
+fn query<T: Default>(f: impl FnOnce(&FunctionMap) -> Result<T, String>) -> T {
+    let functions = with_frame(|frame| frame.functions.clone());
+    match f(&functions) {
+        Ok(value) => value,
+        Err(error) => {
+            with_frame(|frame| {
+                if frame.error.is_none() {
+                    frame.error = Some(error);
+                }
+            });
+            T::default()
+        }
+    }
+}
+
+/// infer the types of the expressions, returning the
+/// annotated program along with the type of each.
+fn infer(
+    functions: &FunctionMap,
+    expressions: &[Token],
+) -> Result<(AnnotatedFunctionMap, Vec<Type>), String> {
+    let mut program = functions.clone();
+    let mut main = vec![];
+    for (i, expression) in expressions.iter().enumerate() {
+        let name = format!("{}-{}", REFLECTED_FUNCTION, i);
+        program.insert(
+            name.clone(),
+            Rc::new(UnparsedFunction::new(
+                vec![],
+                Token::Expression(vec![symbol("return"), expression.clone()]),
+            )),
+        );
+        main.push(Token::Expression(vec![symbol(&name)]));
+    }
+    program.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(vec![], Token::Block(main))),
+    );
+    let annotated = match annotate_types(&mut Compiler::new(), &program) {
+        Ok(annotated) => annotated,
+        Err(e) => {
+            return Err(format!(
+                "unable to infer the type of {}: {}",
+                expressions[0], e
+            ))
+        }
+    };
+    let mut types = vec![];
+    for i in 0..expressions.len() {
+        let name = format!("{}-{}", REFLECTED_FUNCTION, i);
+        types.push(annotated[&name][&vec![]].return_type.clone());
+    }
+    Ok((annotated, types))
+}
+
+fn signature(functions: &FunctionMap, call: &Token) -> Result<String, String> {
+    let (name, args) = match call.unlocated() {
+        Token::Expression(ref tokens) if !tokens.is_empty() => match tokens[0].unlocated() {
+            Token::Symbol(ref name) => (unmark(name).to_owned(), &tokens[1..]),
+            _ => return Err(format!("{} is not a function call", call)),
+        },
+        _ => return Err(format!("{} is not a function call", call)),
+    };
+    let mut expressions = vec![call.clone()];
+    expressions.extend(args.iter().cloned());
+    let (annotated, types) = infer(functions, &expressions)?;
+    let (arg_types, return_type) = match annotated.get(&name).and_then(|f| f.get(&types[1..])) {
+        Some(function) => (function.arg_types.clone(), function.return_type.clone()),
+        // builtins are not annotated.
+        None => (types[1..].to_vec(), types[0].clone()),
+    };
+    let arg_types: Vec<String> = arg_types.iter().map(|t| t.to_string()).collect();
+    Ok(format!("[{}] -> {}", arg_types.join(" "), return_type))
+}
+
+fn symbol(name: &str) -> Token {
+    Token::Symbol(Box::new(String::from(name)))
+}
+
+/// whether a function or builtin of the name exists.
+#[no_mangle]
+pub extern "C" fn function_exists(name: *const c_char) -> bool {
+    let name = to_str(name);
+    get_builtin_expressions().contains_key(name)
+        || query(|functions| Ok(functions.contains_key(name)))
+}
+
+#[no_mangle]
+pub extern "C" fn function_arity(name: *const c_char) -> i64 {
+    let name = to_str(name);
+    query(|functions| match functions.get(name) {
+        Some(function) => Ok(function.args.len() as i64),
+        None => Err(format!("function-arity: no function named {}", name)),
+    })
+}
+
+/// the signature of the function the call expression
+/// invokes, with the types of the arguments passed.
+#[no_mangle]
+pub extern "C" fn function_signature(call: *mut Token) -> *const c_char {
+    let call = unsafe { (*call).clone() };
+    to_c_string(query(|functions| match signature(functions, &call) {
+        Ok(signature) => Ok(signature),
+        Err(e) => Err(format!("function-signature: {}", e)),
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn type_of(token: *mut Token) -> *const c_char {
+    let token = unsafe { (*token).clone() };
+    to_c_string(query(|functions| match infer(functions, &[token]) {
+        Ok((_, types)) => Ok(types[0].to_string()),
+        Err(e) => Err(format!("type-of: {}", e)),
+    }))
+}
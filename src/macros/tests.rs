@@ -0,0 +1,279 @@
+use super::super::parse;
+use super::{
+    apply_macros_to_function_map, parse_macro, ExpansionContext, MacroMap, Token, UnparsedFunction,
+};
+use std::{collections::HashMap, rc::Rc};
+
+/// parse all macros declared in the source.
+fn parse_macros(source: &str) -> MacroMap {
+    let mut macros = MacroMap::new();
+    if let Token::Block(tokens) = parse(source) {
+        for token in tokens {
+            if let Token::Expression(e) = token {
+                let (name, macro_instance) = parse_macro(e).unwrap();
+                macros.insert(name, macro_instance);
+            }
+        }
+    }
+    macros
+}
+
+/// expand the body as the main function, returning the
+/// resulting body.
+fn expand(macros: &MacroMap, body: &str) -> Token {
+    let mut functions = HashMap::new();
+    functions.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(vec![], parse(body))),
+    );
+    let mut context = ExpansionContext::new(macros.clone());
+    apply_macros_to_function_map(&mut context, &mut functions).unwrap();
+    functions.get("main").unwrap().body.clone()
+}
+
+/// return the names of all variables bound via let.
+fn let_bindings(token: &Token, bindings: &mut Vec<String>) {
+    match token {
+        Token::Expression(ref e) => {
+            if let (Some(&Token::Symbol(ref head)), Some(&Token::Symbol(ref name))) =
+                (e.get(0), e.get(1))
+            {
+                if **head == "let" {
+                    bindings.push((**name).clone());
+                }
+            }
+            for t in e {
+                let_bindings(t, bindings);
+            }
+        }
+        Token::Block(ref tl) | Token::List(ref tl) => {
+            for t in tl {
+                let_bindings(t, bindings);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_macro_expansion() {
+    let macros = parse_macros("macro! neq [l r] (not (eq l r))");
+    assert_eq!(expand(&macros, "neq 1 2"), parse("not (eq 1 2)"));
+}
+
+#[test]
+fn test_macro_bindings_do_not_clash() {
+    let macros = parse_macros("macro! twice [body] <(let i 0) body body>");
+    let mut bindings = vec![];
+    let_bindings(&expand(&macros, "let i 10\ntwice (print i)"), &mut bindings);
+    assert_eq!(bindings.len(), 2);
+    assert_eq!(bindings[0], "i");
+    assert!(bindings[1] != "i");
+    assert!(bindings[1].starts_with("i#"));
+}
+
+#[test]
+fn test_nested_macro_bindings_do_not_clash() {
+    let macros = parse_macros(
+        "macro! loop-from [symbol start body] <(let symbol start) body>
+macro! loop [body] (loop-from i 0 body)",
+    );
+    let result = expand(&macros, "let i 10\nloop (print i)");
+    let mut bindings = vec![];
+    let_bindings(&result, &mut bindings);
+    assert_eq!(bindings[0], "i");
+    assert!(bindings[1].starts_with("i#"));
+    // the body still references the variable at the call site.
+    assert_eq!(
+        result,
+        Token::Block(vec![
+            parse_statement("let i 10"),
+            Token::Block(vec![
+                Token::Expression(vec![symbol("let"), symbol(&bindings[1]), Token::Integer(0)]),
+                parse_statement("print i"),
+            ]),
+        ])
+    );
+}
+
+#[test]
+fn test_macro_capture() {
+    let macros = parse_macros("macro! set-x [value] (let (capture x) value)");
+    assert_eq!(expand(&macros, "set-x 1"), parse("let x 1"));
+}
+
+#[test]
+fn test_macro_gensym() {
+    let macros =
+        parse_macros("macro! swap [a b] <(let (gensym tmp) a) (let a b) (let b (gensym tmp))>");
+    let result = expand(&macros, "swap x y");
+    let mut bindings = vec![];
+    let_bindings(&result, &mut bindings);
+    assert!(bindings[0].starts_with("tmp#"));
+    assert_eq!(bindings[1], "x");
+    assert_eq!(bindings[2], "y");
+}
+
+#[test]
+fn test_macro_rest_parameter() {
+    let macros = parse_macros("macro! when [cond & body] (while cond <(splice body) (break)>)");
+    assert_eq!(
+        expand(&macros, "when true (print 1) (print 2)"),
+        parse("while true <(print 1) (print 2) (break)>")
+    );
+    assert_eq!(expand(&macros, "when true"), parse("while true <(break)>"));
+}
+
+#[test]
+fn test_macro_splice_into_expression() {
+    let macros = parse_macros("macro! call [function & args] (function 1 (splice args))");
+    assert_eq!(expand(&macros, "call + 2"), parse("+ 1 2"));
+}
+
+#[test]
+fn test_macro_rest_parameter_requires_positional_arguments() {
+    let macros = parse_macros("macro! when [cond & body] (while cond <(splice body)>)");
+    let mut functions = HashMap::new();
+    functions.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(vec![], parse("when"))),
+    );
+    let mut context = ExpansionContext::new(macros);
+    assert!(apply_macros_to_function_map(&mut context, &mut functions).is_err());
+}
+
+#[test]
+fn test_pattern_macro() {
+    let macros = parse_macros(
+        "macro! loop rules [from to]
+\t[symbol from start to end body] (for (let symbol start) (neq symbol end) (++ symbol) body)
+\t[cond body] (while cond body)",
+    );
+    assert_eq!(
+        expand(&macros, "loop i from 0 to 10 (print i)"),
+        parse("for (let i 0) (neq i 10) (++ i) (print i)")
+    );
+    assert_eq!(
+        expand(&macros, "loop true (print 1)"),
+        parse("while true (print 1)")
+    );
+}
+
+#[test]
+fn test_pattern_macro_nested_pattern() {
+    let macros = parse_macros(
+        "macro! swap-pair rules []
+\t[(first second)] (second first)",
+    );
+    assert_eq!(expand(&macros, "swap-pair (1 print)"), parse("print 1"));
+}
+
+#[test]
+fn test_pattern_macro_repetition() {
+    let macros = parse_macros(
+        "macro! let-all rules []
+\t[& (name value)] <& (let name value)>",
+    );
+    assert_eq!(
+        expand(&macros, "let-all (a 1) (b 2)"),
+        Token::Block(vec![parse("let a 1\nlet b 2")])
+    );
+}
+
+#[test]
+fn test_pattern_macro_without_match() {
+    let macros = parse_macros(
+        "macro! one-arg rules []
+\t[value] (print value)",
+    );
+    let mut functions = HashMap::new();
+    functions.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(vec![], parse("one-arg 1 2"))),
+    );
+    let mut context = ExpansionContext::new(macros);
+    assert!(apply_macros_to_function_map(&mut context, &mut functions).is_err());
+}
+
+#[test]
+fn test_pattern_binding_a_variable_twice() {
+    let error = match parse_statement("macro! pairs rules []\n\t[& (x x)] (print x)") {
+        Token::Expression(e) => parse_macro(e).unwrap_err(),
+        t => panic!("expected an expression. found {}", t),
+    };
+    assert_eq!(
+        format!("{}", error),
+        "the pattern [& (x x ) ] binds x more than once"
+    );
+}
+
+#[test]
+fn test_pattern_repetition_needs_a_single_subpattern() {
+    for pattern in &["[a & b c]", "[a &]", "[(& x) & &]"] {
+        let source = format!("macro! m rules []\n\t{} (print a)", pattern);
+        let error = match parse_statement(&source) {
+            Token::Expression(e) => parse_macro(e).unwrap_err(),
+            t => panic!("expected an expression. found {}", t),
+        };
+        assert!(format!("{}", error).starts_with("expected a single subpattern after &"));
+    }
+}
+
+#[test]
+fn test_recursive_macro_exceeds_depth() {
+    let macros = parse_macros("macro! loop [x] (loop x)");
+    let mut functions = HashMap::new();
+    functions.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(vec![], parse("loop 1"))),
+    );
+    let mut context = ExpansionContext::new(macros);
+    context.max_depth = 10;
+    let error = apply_macros_to_function_map(&mut context, &mut functions).unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "macro expansion exceeded the maximum depth of 10. expansion chain: loop (x11)"
+    );
+}
+
+#[test]
+fn test_mutually_recursive_macro_chain() {
+    let macros = parse_macros("macro! ping [x] (pong x)\nmacro! pong [x] (ping x)");
+    let mut functions = HashMap::new();
+    functions.insert(
+        String::from("main"),
+        Rc::new(UnparsedFunction::new(vec![], parse("ping 1"))),
+    );
+    let mut context = ExpansionContext::new(macros);
+    context.max_depth = 20;
+    let error = format!(
+        "{}",
+        apply_macros_to_function_map(&mut context, &mut functions).unwrap_err()
+    );
+    assert!(error.ends_with("expansion chain: ... -> pong -> ping -> pong -> ping -> pong -> ping -> pong -> ping -> pong -> ping"));
+}
+
+#[test]
+fn test_procedural_macro() {
+    let macros = parse_macros(
+        "proc-macro! reverse-call [expr]
+\tlet result (make-token \"expression\")
+\tlet i (token-len expr)
+\twhile (not (eq i 0))
+\t\tlet i (- i 1)
+\t\ttoken-push result (token-get expr i)
+\treturn result",
+    );
+    assert_eq!(expand(&macros, "reverse-call (1 print)"), parse("print 1"));
+}
+
+fn parse_statement(source: &str) -> Token {
+    match parse(source) {
+        Token::Block(mut tokens) => tokens.remove(0),
+        t => t,
+    }
+}
+
+fn symbol(name: &str) -> Token {
+    Token::Symbol(Box::new(String::from(name)))
+}
@@ -1,5 +1,7 @@
 #![feature(plugin)]
 #![feature(duration_float)]
+extern crate getopts;
+extern crate indexmap;
 extern crate pest;
 #[macro_use]
 extern crate pest_derive;
@@ -12,6 +14,7 @@ mod ast;
 mod compiler;
 mod error;
 mod expressions;
+mod formatter;
 mod function_loader;
 mod llvm_builder;
 mod llvm_codegen;
@@ -24,57 +27,168 @@ mod types;
 mod workflow;
 
 use self::array::Array;
-use self::ast::Token;
+use self::ast::{Expansion, HashableToken, Location, Map, Token};
 use self::compiler::CompilerData;
 use self::error::{DispError, DispResult, GenericError, GenericResult};
 use self::types::{Type, TypeSet};
 // Exporting all functions publicy, so they will
 // be discovered by llvm.
-use self::expressions::{get_builtin_expressions, BuiltinExpressions};
-use self::function_loader::{parse_functions_and_macros, FunctionMap, UnparsedFunction};
-use self::llvm_builder::{Builder, LLVMInstruction};
+use self::expressions::{
+    get_builtin_expressions, map_entry, partial_match, runtime_error, token_to_ptr,
+    BuiltinExpressions,
+};
+use self::function_loader::{link_modules, load_module, FunctionMap, UnparsedFunction};
+use self::llvm_builder::{Builder, LLVMFunction, LLVMInstruction};
 pub use self::llvm_codegen::{
     build_functions, to_ptr, CodegenError, Compiler, Context, Function, FunctionType, LLVMCompiler,
     LLVMTypeCache, NativeFunction, Object, Scope,
 };
-use self::loader::load_file;
-use self::macros::{apply_macros_to_function_map, parse_macro, MacroMap};
-use self::parser::parse;
-use self::stdlib::LIB_FILE;
+use self::macros::{
+    apply_macros_to_function_map, expand_macro_call, own_token, parse_macro, unmark,
+    ExpansionContext, MacroMap, Module, DEFAULT_MAX_EXPANSION_DEPTH,
+};
+use self::parser::{parse, parse_file};
+use self::stdlib::STDLIB_MODULE;
 use self::type_annotator::{
-    annotate_types, AnnotatedFunction, AnnotatedFunctionMap, TypecheckType, TypevarFunction,
-    AnnotatorScope
+    annotate_types, check_types, map_address, AnnotatedFunction, AnnotatedFunctionMap,
+    TypecheckType, TypevarFunction, AnnotatorScope
 };
-use self::workflow::load_string_into_compiler;
+use self::formatter::{format_block, format_statement};
+use self::workflow::{check_program, expand_program, load_program_into_compiler};
+use getopts::Options;
 use std::{
     env,
     fs::File,
     io::{self, Read, Write},
 };
-// use stdlib::load_stdlib;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let result = match args.len() {
-        2 => execute(&args[1]),
-        _ => {panic!("no repl atm.")}
-        // _ => repl(),
+    let mut opts = Options::new();
+    opts.optopt("f", "function", "expand: only show the function NAME", "NAME");
+    opts.optflag("s", "step", "expand: show every individual macro expansion");
+    opts.optopt(
+        "",
+        "max-expansion-depth",
+        "the maximum number of nested macro expansions",
+        "DEPTH",
+    );
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(e) => panic!("{}", e),
+    };
+    let max_expansion_depth = match matches.opt_str("max-expansion-depth") {
+        Some(depth) => depth
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid expansion depth {}", depth)),
+        None => DEFAULT_MAX_EXPANSION_DEPTH,
+    };
+    let result = match matches.free.len() {
+        1 => execute(&matches.free[0], max_expansion_depth),
+        2 if matches.free[0] == "expand" => expand(
+            &matches.free[1],
+            matches.opt_str("function"),
+            matches.opt_present("step"),
+            max_expansion_depth,
+        ),
+        2 if matches.free[0] == "check" => check(&matches.free[1], max_expansion_depth),
+        _ => {
+            print!(
+                "{}",
+                opts.usage("usage: disp FILE\n       disp expand FILE [options]\n       disp check FILE")
+            );
+            Ok(())
+        }
     };
     if let Err(ref message) = result {
         panic!("{}", message);
     }
 }
 
-fn execute(path: &str) -> Result<(), GenericError> {
-    let mut compiler = Compiler::new();
+/// parse the file. The standard lib, and any other
+/// modules, are loaded when they are imported.
+fn read_program(path: &str) -> Result<Token, GenericError> {
     let mut input = String::new();
-    // load the standard lib
-    let mut stdlib = File::open(LIB_FILE)?;
-    stdlib.read_to_string(&mut input)?;
-    // load the main file
     let mut file = File::open(path)?;
     file.read_to_string(&mut input)?;
-    load_string_into_compiler(&mut compiler, &input)?;
+    Ok(parse_file(&input, path))
+}
+
+fn execute(path: &str, max_expansion_depth: usize) -> Result<(), GenericError> {
+    let mut compiler = Compiler::new();
+    let program = read_program(path)?;
+    load_program_into_compiler(&mut compiler, program, max_expansion_depth)?;
+    Ok(())
+}
+
+/// print the source of every function, after
+/// macros have been expanded.
+fn expand(
+    path: &str,
+    function: Option<String>,
+    step: bool,
+    max_expansion_depth: usize,
+) -> Result<(), GenericError> {
+    let program = read_program(path)?;
+    let (functions, context) = expand_program(program, step, max_expansion_depth)?;
+    let selected = |name: &str| match function {
+        Some(ref f) => f == name,
+        None => true,
+    };
+    for (i, step) in context.steps.iter().enumerate() {
+        // top-level statements are a part of main.
+        let (function_name, location) = match step.function {
+            Some(ref f) => (f.as_str(), f.as_str()),
+            None => ("main", "main (top level)"),
+        };
+        if !selected(function_name) {
+            continue;
+        }
+        println!("# step {}: expanding {} in {}", i + 1, step.macro_name, location);
+        println!("{}", format_statement(&step.call, 0));
+        println!("# =>");
+        println!("{}\n", format_block(&[step.result.clone()], 0));
+    }
+    let mut names: Vec<&String> = functions.keys().filter(|n| selected(n)).collect();
+    // main is written last, as top-level statements.
+    names.sort_by_key(|n| (*n == "main", (*n).clone()));
+    for name in names {
+        let function = &functions[name];
+        let statements = match *function.body.unlocated() {
+            Token::Block(ref statements) => statements.clone(),
+            ref t => vec![t.clone()],
+        };
+        if name == "main" {
+            println!("{}", format_block(&statements, 0));
+        } else {
+            println!("fn {} [{}]", name, function.args.join(" "));
+            println!("{}\n", format_block(&statements, 1));
+        }
+    }
+    Ok(())
+}
+
+/// type check every function, without running the program.
+/// The signature of every function is printed after the name
+/// of its module, or the error it failed with.
+fn check(path: &str, max_expansion_depth: usize) -> Result<(), GenericError> {
+    let program = read_program(path)?;
+    let mut failed = 0;
+    for (module, name, result) in check_program(program, max_expansion_depth)? {
+        match result {
+            Ok(signature) => println!("{}.{}: {}", module, name, signature),
+            Err(error) => {
+                failed += 1;
+                println!("{}.{}: error: {}", module, name, error);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Box::new(DispError::new(&format!(
+            "{} function(s) failed to type check",
+            failed
+        ))));
+    }
     Ok(())
 }
 
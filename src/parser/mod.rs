@@ -1,7 +1,7 @@
-use super::Token;
+use super::{Location, Map, Token};
 /// contains all the parsing structures of ghvm
 use pest::{iterators::Pair, Parser};
-use std::collections::HashMap;
+use std::rc::Rc;
 
 #[derive(Parser)]
 #[grammar = "parser/grammar_indented.pest"]
@@ -14,20 +14,30 @@ pub fn parse(body: &str) -> Token {
     parse_rule(Rule::head, body)
 }
 
+/// parse the contents of a file. Every line is
+/// annotated with its location in the file.
+pub fn parse_file(body: &str, file: &str) -> Token {
+    parse_rule_in_file(Rule::head, body, Some(&Rc::new(String::from(file))))
+}
+
 fn parse_rule(rule: Rule, body: &str) -> Token {
+    parse_rule_in_file(rule, body, None)
+}
+
+fn parse_rule_in_file(rule: Rule, body: &str, file: Option<&Rc<String>>) -> Token {
     let mut pairs = DispParser::parse(rule, &body).unwrap_or_else(|e| panic!("{}", e));
     if let Some(pair) = pairs.next() {
         if cfg!(feature = "debug") {
             println!("DEBUG pest parser result: {:?}", pair.clone());
             println!("DEBUG pest string: {:?}", pair.clone().as_span().as_str());
         }
-        return unpack(pair);
+        return unpack(pair, file);
     }
     return Token::None;
 }
 
 /// Convert a token from the parser to a Disp token
-fn unpack(pair: Pair<Rule>) -> Token {
+fn unpack(pair: Pair<Rule>, file: Option<&Rc<String>>) -> Token {
     match pair.clone().as_rule() {
         _s @ Rule::bang_symbol => Token::BangSymbol(Box::new(String::from(pair.as_str()))),
         // _c @ Rule::comment => Token::Comment(Box::new(String::from(pair.as_str()))),
@@ -35,47 +45,59 @@ fn unpack(pair: Pair<Rule>) -> Token {
         _e @ Rule::expression_no_parens => {
             let mut tokens = vec![];
             for p in pair.into_inner() {
-                tokens.push(unpack(p));
+                tokens.push(unpack(p, file));
             }
             Token::Expression(tokens)
         }
         _e @ Rule::expression => {
             let mut tokens = vec![];
             for p in pair.into_inner() {
-                tokens.push(unpack(p));
+                tokens.push(unpack(p, file));
             }
             Token::Expression(tokens)
         }
         _f @ Rule::false_value => Token::Boolean(false),
+        _f @ Rule::float => Token::Float(pair.as_str().parse::<f64>().unwrap()),
         _e @ Rule::integer => Token::Integer(pair.as_str().parse::<i64>().unwrap()),
         _l @ Rule::list_of_lines => {
             let mut tokens = vec![];
             for p in pair.into_inner() {
-                tokens.push(unpack(p));
+                let line = p.as_span().start_pos().line_col().0;
+                tokens.push(match (unpack(p, file), file) {
+                    // comments are not located.
+                    (Token::None, _) => Token::None,
+                    (token, Some(file)) => {
+                        Token::Located(Box::new(Location::new(file.clone(), line)), Box::new(token))
+                    }
+                    (token, None) => token,
+                });
             }
             Token::Block(tokens)
         }
         _l @ Rule::list => {
             let mut tokens = vec![];
             for p in pair.into_inner() {
-                tokens.push(unpack(p));
+                tokens.push(unpack(p, file));
             }
             Token::List(tokens)
         }
         _l @ Rule::block => {
             let mut tokens = vec![];
             for p in pair.into_inner() {
-                tokens.push(unpack(p));
+                tokens.push(unpack(p, file));
             }
             Token::Block(tokens)
         }
         _m @ Rule::map => {
-            let mut map = HashMap::new();
+            let mut map = Map::new();
             let mut pairs = pair.into_inner();
             let mut maybe_key = pairs.next();
             while let Some(key) = maybe_key {
                 let value = pairs.next().expect("value not found corresponding to key");
-                map.insert(unpack(key).to_hashable().unwrap(), unpack(value));
+                let key = unpack(key, file)
+                    .to_hashable()
+                    .unwrap_or_else(|e| panic!("{}", e));
+                map.insert(key, unpack(value, file));
                 maybe_key = pairs.next();
             }
             Token::Map(Box::new(map))
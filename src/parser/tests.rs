@@ -1,11 +1,16 @@
-use super::{parse, parse_rule, Rule, Token};
-use std::collections::HashMap;
+use super::{parse, parse_file, parse_rule, Location, Map, Rule, Token};
+use std::rc::Rc;
 
 #[test]
 fn test_parser_integer() {
     assert_eq!(parse_rule(Rule::token, "10"), Token::Integer(10));
 }
 
+#[test]
+fn test_parser_float() {
+    assert_eq!(parse_rule(Rule::token, "10.25"), Token::Float(10.25));
+}
+
 #[test]
 fn test_parser_symbol() {
     assert_eq!(
@@ -16,6 +21,29 @@ fn test_parser_symbol() {
         parse_rule(Rule::token, "foobar-dash"),
         Token::Symbol(Box::new(String::from("foobar-dash")))
     );
+    assert_eq!(
+        parse_rule(Rule::token, "u64"),
+        Token::Symbol(Box::new(String::from("u64")))
+    );
+    assert_eq!(
+        parse_rule(Rule::token, "<="),
+        Token::Symbol(Box::new(String::from("<=")))
+    );
+}
+
+#[test]
+fn test_parser_block_ends_at_greater_than() {
+    assert_eq!(
+        parse_rule(Rule::token, "<foo (> 1 2)>"),
+        Token::Block(vec![
+            Token::Symbol(Box::new(String::from("foo"))),
+            Token::Expression(vec![
+                Token::Symbol(Box::new(String::from(">"))),
+                Token::Integer(1),
+                Token::Integer(2),
+            ]),
+        ])
+    );
 }
 
 #[test]
@@ -43,7 +71,17 @@ fn test_parser_list() {
 fn test_parser_empty_map() {
     assert_eq!(
         parse_rule(Rule::token, "{}"),
-        Token::Map(Box::new(HashMap::new()))
+        Token::Map(Box::new(Map::new()))
+    );
+}
+
+#[test]
+fn test_parser_float_key() {
+    let mut m = Map::new();
+    m.insert(Token::Float(1.5).to_hashable().unwrap(), Token::Integer(1));
+    assert_eq!(
+        parse_rule(Rule::token, "{1.5: 1,}"),
+        Token::Map(Box::new(m))
     );
 }
 
@@ -126,7 +164,7 @@ fn test_parser_indented_list() {
 
 #[test]
 fn test_parser_map() {
-    let mut m = HashMap::new();
+    let mut m = Map::new();
     m.insert(
         Token::Boolean(true).to_hashable().unwrap(),
         Token::Integer(1),
@@ -143,7 +181,7 @@ fn test_parser_map() {
 
 #[test]
 fn test_parser_multiline_map() {
-    let mut m = HashMap::new();
+    let mut m = Map::new();
     m.insert(
         Token::Boolean(true).to_hashable().unwrap(),
         Token::Integer(1),
@@ -162,3 +200,30 @@ fn test_parser_multiline_map() {
         Token::List(vec![Token::Expression(vec![Token::Map(Box::new(m))]),]),
     );
 }
+
+#[test]
+fn test_parse_file_locates_lines() {
+    let file = Rc::new(String::from("a.ds"));
+    let located =
+        |line, token| Token::Located(Box::new(Location::new(file.clone(), line)), Box::new(token));
+    assert_eq!(
+        parse_file("let x 1\n\nprint x", "a.ds"),
+        Token::Block(vec![
+            located(
+                1,
+                Token::Expression(vec![
+                    Token::Symbol(Box::new(String::from("let"))),
+                    Token::Symbol(Box::new(String::from("x"))),
+                    Token::Integer(1),
+                ])
+            ),
+            located(
+                3,
+                Token::Expression(vec![
+                    Token::Symbol(Box::new(String::from("print"))),
+                    Token::Symbol(Box::new(String::from("x"))),
+                ])
+            ),
+        ])
+    );
+}
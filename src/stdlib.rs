@@ -1,16 +1,3 @@
-use super::{load_file, Compiler, GenericError};
-
-// for now, we can emulate a standard library
-// by just loading a lib file before executing the file in question.
-pub const LIB_FILE: &'static str = "lib.ds";
-
-// load the standard library
-// pub fn load_stdlib(vm: &mut VM) -> Result<(), DispError> {
-//     exec_file(vm, LIB_FILE)
-// }
-
-pub fn load_stdlib<'a>(compiler: &mut Compiler<'a>) -> Result<(), GenericError> {
-    load_file(compiler, LIB_FILE, "std")?;
-    // we do not execute the stdlib file.
-    Ok(())
-}
+/// the module of the standard library. The macros it
+/// exports are available within every module.
+pub const STDLIB_MODULE: &'static str = "lib";
@@ -1,9 +1,19 @@
 use super::{
-    Compiler, DispError, DispResult, FunctionMap, GenericResult, Token, Type, UnparsedFunction,
+    map_entry, partial_match, Compiler, DispError, DispResult, FunctionMap, GenericResult,
+    HashableToken, Location, Map, Token, Type, UnparsedFunction,
+};
+use inference::{
+    Constraint, Origin, Resolved, Source, TypeResolver, TypeScheme, TypeVar, Unresolved,
+};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ptr,
+    rc::Rc,
 };
-use inference::{Constraint, TypeResolver, TypeVar, Unresolved};
-use std::{collections::HashMap, rc::Rc};
 mod scope;
+#[cfg(test)]
+mod tests;
 mod types;
 pub use self::scope::AnnotatorScope;
 pub use self::types::{to_type, TypecheckType};
@@ -17,35 +27,111 @@ pub struct AnnotatedFunction {
     pub function: Rc<UnparsedFunction>,
     pub arg_types: Vec<Type>,
     pub return_type: Type,
+    /// the types of the map literals in the body, by their
+    /// address. An empty map literal gets its types from
+    /// how it is used, so they can not be found in codegen.
+    pub map_types: HashMap<usize, Type>,
 }
 
 /// Internal data structure to keep
 /// track of all of the annotated functions.
+///
+/// Functions are generic: once a function has been annotated,
+/// its types are generalized into a scheme, and every call
+/// creates a new instance of it. This allows a function
+/// to be called with different types. Every instance with
+/// a distinct set of argument types is a specialization
+/// of the function.
 struct TypevarFunctionMap {
-    pub map: HashMap<String, HashMap<usize, Rc<TypevarFunction>>>,
+    /// the functions that are being annotated. Their types are
+    /// not generalized yet, so recursive calls use them as is.
+    pub map: HashMap<String, Rc<TypevarFunction>>,
+    pub schemes: HashMap<String, FunctionScheme>,
+    /// the names of the functions that are being
+    /// annotated, innermost last.
+    annotating: Vec<String>,
+    /// the instances of functions called by each function
+    /// that is being annotated, innermost last.
+    calls: Vec<Vec<FunctionInstance>>,
+    /// functions that failed to type check. When type checking
+    /// the whole program, calls to them are not checked, so
+    /// the error is only reported once.
+    failed: HashSet<String>,
+}
+
+/// A function called with specific types.
+#[derive(Clone)]
+struct FunctionInstance {
+    name: String,
+    function: Rc<TypevarFunction>,
+}
+
+/// The generalized types of an annotated function. The
+/// scheme includes the types of the function, followed
+/// by the types of every function instance it calls.
+struct FunctionScheme {
+    function: Rc<TypevarFunction>,
+    calls: Vec<FunctionInstance>,
+    scheme: TypeScheme,
 }
 
 impl<'a> TypevarFunctionMap {
     pub fn new() -> TypevarFunctionMap {
         TypevarFunctionMap {
             map: HashMap::new(),
+            schemes: HashMap::new(),
+            annotating: vec![],
+            calls: vec![],
+            failed: HashSet::new(),
         }
     }
 
-    pub fn insert(&mut self, name: String, arg_len: usize, function: Rc<TypevarFunction>) {
-        let function_by_typevar = self.map.entry(name).or_insert(HashMap::new());
-        function_by_typevar.insert(arg_len, function);
+    /// start annotating the function.
+    pub fn insert(&mut self, name: String, function: Rc<TypevarFunction>) {
+        self.annotating.push(name.clone());
+        self.map.insert(name, function);
+        self.calls.push(vec![]);
     }
 
-    pub fn get(&self, name: &String, arg_len: usize) -> Option<&TypevarFunction> {
-        match self.map.get(name) {
-            None => None,
-            Some(ref function_by_typevar) => match function_by_typevar.get(&arg_len) {
-                None => None,
-                Some(ref function) => Some(function),
-            },
+    /// finish annotating the function, returning
+    /// the instances of the functions it calls.
+    pub fn remove(&mut self, name: &String) -> Vec<FunctionInstance> {
+        self.map.remove(name);
+        self.annotating.pop();
+        self.calls.pop().unwrap_or(vec![])
+    }
+
+    /// stop annotating all functions, after an error. The innermost
+    /// function, which the error occurred in, is returned.
+    fn abort(&mut self) -> Option<String> {
+        let failed = self.annotating.pop();
+        self.map.clear();
+        self.annotating.clear();
+        self.calls.clear();
+        failed
+    }
+
+    /// record an instance called by the function
+    /// that is being annotated.
+    fn add_call(&mut self, instance: FunctionInstance) {
+        if let Some(calls) = self.calls.last_mut() {
+            calls.push(instance);
         }
     }
+
+    /// the types of all functions that are being annotated.
+    /// These may not be generalized.
+    fn monomorphic_types(&self) -> Vec<TypeVar> {
+        let mut types = vec![];
+        for function in self.map.values() {
+            types.extend(function.type_vars());
+        }
+        types
+    }
+
+    pub fn get(&self, name: &String) -> Option<&TypevarFunction> {
+        self.map.get(name).map(|function| &**function)
+    }
 }
 
 /// Annotated functions have Type variables attached,
@@ -56,6 +142,9 @@ pub struct TypevarFunction {
     pub function: Rc<UnparsedFunction>,
     pub arg_types: Vec<TypeVar>,
     pub return_type: TypeVar,
+    /// the types of the map literals in the body, by their
+    /// address, with the location of each literal.
+    map_types: RefCell<Vec<(usize, TypeVar, Option<Location>)>>,
 }
 
 impl TypevarFunction {
@@ -68,9 +157,21 @@ impl TypevarFunction {
             function,
             arg_types,
             return_type,
+            map_types: RefCell::new(vec![]),
         };
     }
 
+    /// the argument types, followed by the return
+    /// type and the types of the map literals.
+    fn type_vars(&self) -> Vec<TypeVar> {
+        let mut type_vars = self.arg_types.clone();
+        type_vars.push(self.return_type);
+        for (_, map_type, _) in self.map_types.borrow().iter() {
+            type_vars.push(*map_type);
+        }
+        type_vars
+    }
+
     pub fn to_annotated_function(
         &self,
         type_resolver: &TypeResolver<TypecheckType>,
@@ -100,10 +201,24 @@ impl TypevarFunction {
             }
             arg_types
         };
+        let mut map_types = HashMap::new();
+        for (map, type_var, _) in self.map_types.borrow().iter() {
+            let typ = match type_resolver.get_type(type_var) {
+                Some(t) => t,
+                None => {
+                    return Err(DispError::new(&format!(
+                        "unable to resolve type variable {} for a map literal",
+                        type_var,
+                    )))
+                }
+            };
+            map_types.insert(*map, to_type(&typ)?);
+        }
         Ok(AnnotatedFunction {
             function: self.function.clone(),
             return_type: to_type(&return_type)?,
             arg_types: arg_types,
+            map_types: map_types,
         })
     }
 }
@@ -125,57 +240,175 @@ pub fn annotate_types(
     // as all functions can have untyped arguments, we should
     // start with the code that will actually be executed. i.e. main
     // functions only.
-    // TODO: have a more robust way to detect main functions.
+    // every instance that is executed: the main functions,
+    // and the instances they call.
+    let mut instances = vec![];
     for (name, function) in functions {
-        if name.contains("main") {
-            let main = Rc::new(TypevarFunction::new(
-                function.clone(),
-                vec![],
-                type_resolver.create_type_var(),
-            ));
-            type_resolver.add_constraint(Constraint::IsLiteral(
-                (*main).return_type,
-                Unresolved::Literal(TypecheckType::None),
-            ))?;
-            annotated_functions.insert((*name).to_owned(), 0, main.clone());
-            let mut scope = AnnotatorScope::new();
-            if cfg!(feature = "debug") {
-                println!("annotating function {:?}\nbody:\n{:?}", name, function.body);
-            }
-            annotate_token(
+        if *name == "main" {
+            instances.extend(annotate_main(
                 compiler,
-                &functions,
+                functions,
                 &mut type_resolver,
                 &mut annotated_functions,
-                &main,
-                &function.body,
-                &mut scope,
-            )?;
+                name,
+                function,
+            )?);
         }
     }
-    // after this point. we have in annotated_functions all methods that are actually
+    // after this point. we have all instances that are actually
     // invoked. We can now convert those into annotated types with concrete
     // type variables.
     let mut result = AnnotatedFunctionMap::new();
-    for (name, function_by_args) in &annotated_functions.map {
-        for typevar_function in function_by_args.values() {
-            let annotated_function = match typevar_function.to_annotated_function(&type_resolver) {
-                Ok(result) => result,
-                Err(error) => {
-                    println!("type resolver: {:?}", type_resolver);
-                    return Err(Box::new(DispError::new(&format!(
-                        "function {}: {}",
-                        name, error
-                    ))));
-                }
+    for instance in &instances {
+        let annotated_function = match instance.function.to_annotated_function(&type_resolver) {
+            Ok(result) => result,
+            Err(error) => {
+                return Err(Box::new(DispError::new(&format!(
+                    "function {}: {}",
+                    instance.name, error
+                ))));
+            }
+        };
+        // instances with the same argument types
+        // share a single specialization.
+        result
+            .entry(instance.name.clone())
+            .or_insert(HashMap::new())
+            .entry(annotated_function.arg_types.clone())
+            .or_insert(annotated_function);
+    }
+    Ok(result)
+}
+
+/// annotate a main function, returning the instances it calls,
+/// followed by the main function itself.
+fn annotate_main(
+    compiler: &mut Compiler,
+    functions: &FunctionMap,
+    types: &mut TypeResolver<TypecheckType>,
+    annotated_functions: &mut TypevarFunctionMap,
+    name: &String,
+    function: &Rc<UnparsedFunction>,
+) -> GenericResult<Vec<FunctionInstance>> {
+    let main = Rc::new(TypevarFunction::new(
+        function.clone(),
+        vec![],
+        types.create_type_var(),
+    ));
+    types.add_constraint(Constraint::IsLiteral(
+        (*main).return_type,
+        Unresolved::Literal(TypecheckType::None),
+    ))?;
+    annotated_functions.insert((*name).to_owned(), main.clone());
+    let mut scope = AnnotatorScope::new();
+    if cfg!(feature = "debug") {
+        println!("annotating function {:?}\nbody:\n{:?}", name, function.body);
+    }
+    annotate_token(
+        compiler,
+        &functions,
+        types,
+        annotated_functions,
+        &main,
+        &function.body,
+        &mut scope,
+    )?;
+    check_map_types(compiler, types, annotated_functions, &main)?;
+    let mut instances = annotated_functions.remove(name);
+    instances.push(FunctionInstance {
+        name: name.clone(),
+        function: main,
+    });
+    Ok(instances)
+}
+
+/// The result of type checking a function: its signature,
+/// or the error it failed with and where it occurred.
+pub struct TypeCheck {
+    pub function: String,
+    pub result: GenericResult<String>,
+    pub location: Option<Location>,
+}
+
+/// type check every function, including those that are never
+/// called. Unlike annotate_types, an error does not stop the
+/// check: every function that fails is reported. Argument and
+/// return types that are not constrained by a function stay
+/// generic, and are named with letters in its signature,
+/// followed by the classes they must be members of.
+pub fn check_types(compiler: &mut Compiler, functions: &FunctionMap) -> Vec<TypeCheck> {
+    let mut type_resolver = TypeResolver::new();
+    let mut annotated_functions = TypevarFunctionMap::new();
+    let mut errors = HashMap::new();
+    let mut names: Vec<&String> = functions.keys().collect();
+    names.sort();
+    let mut checked_mains = HashSet::new();
+    for name in &names {
+        // an error aborts the functions calling the function that
+        // failed, so they are checked again until they succeed.
+        while !annotated_functions.schemes.contains_key(*name)
+            && !annotated_functions.failed.contains(*name)
+            && !checked_mains.contains(*name)
+        {
+            compiler.data.location = None;
+            type_resolver.set_origin(None);
+            let result = if **name == "main" {
+                annotate_main(
+                    compiler,
+                    functions,
+                    &mut type_resolver,
+                    &mut annotated_functions,
+                    name,
+                    &functions[*name],
+                )
+                .map(|_| {
+                    checked_mains.insert(*name);
+                })
+            } else {
+                annotate_function(
+                    compiler,
+                    functions,
+                    &mut type_resolver,
+                    &mut annotated_functions,
+                    name,
+                    &functions[*name],
+                )
             };
-            result
-                .entry(name.clone())
-                .or_insert(HashMap::new())
-                .insert(annotated_function.arg_types.clone(), annotated_function);
+            if let Err(error) = result {
+                let failed = annotated_functions
+                    .abort()
+                    .unwrap_or_else(|| (*name).clone());
+                annotated_functions.failed.insert(failed.clone());
+                errors.insert(failed, (error, compiler.data.location.take()));
+            }
         }
     }
-    Ok(result)
+    let mut checks = vec![];
+    for name in names {
+        let (result, location) = match errors.remove(name) {
+            Some((error, location)) => (Err(error), location),
+            // top-level statements do not return a value.
+            None if checked_mains.contains(name) => (Ok(String::from("[] -> None")), None),
+            None => {
+                let scheme = &annotated_functions.schemes[name];
+                let type_vars = &scheme.scheme.type_vars[..scheme.function.function.args.len() + 1];
+                let mut types = type_resolver.describe_types(type_vars);
+                let return_type = types.pop().unwrap();
+                let mut signature = format!("[{}] -> {}", types.join(" "), return_type);
+                let classes = type_resolver.describe_classes(type_vars);
+                if !classes.is_empty() {
+                    signature += &format!(" where {}", classes.join(", "));
+                }
+                (Ok(signature), None)
+            }
+        };
+        checks.push(TypeCheck {
+            function: name.clone(),
+            result,
+            location,
+        });
+    }
+    checks
 }
 
 fn annotate_token<'a>(
@@ -187,11 +420,46 @@ fn annotate_token<'a>(
     token: &Token,
     scope: &mut AnnotatorScope<'a>,
 ) -> GenericResult<TypeVar> {
+    if let Token::Located(ref location, ref token) = token {
+        // the location is only restored on success, so
+        // errors keep the innermost location.
+        let previous = compiler.data.location.replace((**location).clone());
+        let result = annotate_token(
+            compiler,
+            functions,
+            types,
+            annotated_functions,
+            current_function,
+            token,
+            scope,
+        )?;
+        compiler.data.location = previous;
+        return Ok(result);
+    }
     let type_var = types.create_type_var();
+    // the types of literals are attributed to them.
+    let previous_origin = match token {
+        Token::String(ref s) => Some(format!("\"{}\"", s)),
+        Token::List(_)
+        | Token::Map(_)
+        | Token::Float(_)
+        | Token::Integer(_)
+        | Token::Boolean(_)
+        | Token::None
+        | Token::Bytes(_) => Some(token.to_string()),
+        _ => None,
+    }
+    .map(|literal| types.set_origin(origin(compiler, Source::Expression(literal))));
     match token {
         Token::List(ref token_list) => {
+            // every item must have the type of the first one.
+            let item_type = types.create_type_var();
+            types.add_constraint(Constraint::IsLiteral(
+                type_var.clone(),
+                Unresolved::Generic(TypecheckType::Array, vec![item_type]),
+            ))?;
             for t in token_list {
-                let item_type = annotate_token(
+                let t = annotate_token(
                     compiler,
                     functions,
                     types,
@@ -200,10 +468,7 @@ fn annotate_token<'a>(
                     t,
                     scope,
                 )?;
-                types.add_constraint(Constraint::IsLiteral(
-                    type_var.clone(),
-                    Unresolved::Generic(TypecheckType::Array, vec![item_type]),
-                ))?;
+                types.add_constraint(Constraint::Equality(item_type, t))?;
             }
         }
         Token::Block(ref token_list) => {
@@ -239,6 +504,12 @@ fn annotate_token<'a>(
             )?;
             types.add_constraint(Constraint::Equality(type_var, result))?;
         }
+        Token::Float(_) => {
+            types.add_constraint(Constraint::IsLiteral(
+                type_var.clone(),
+                Unresolved::Literal(TypecheckType::Float),
+            ))?;
+        }
         Token::Integer(_) => {
             types.add_constraint(Constraint::IsLiteral(
                 type_var.clone(),
@@ -251,6 +522,12 @@ fn annotate_token<'a>(
                 Unresolved::Literal(TypecheckType::Bool),
             ))?;
         }
+        Token::None => {
+            types.add_constraint(Constraint::IsLiteral(
+                type_var.clone(),
+                Unresolved::Literal(TypecheckType::None),
+            ))?;
+        }
         Token::String(_) => {
             types.add_constraint(Constraint::IsLiteral(
                 type_var.clone(),
@@ -269,27 +546,26 @@ fn annotate_token<'a>(
             ))?;
         }
         Token::Map(map) => {
-            for (key, value) in map.iter() {
-                annotate_token(
-                    compiler,
-                    functions,
-                    types,
-                    annotated_functions,
-                    current_function,
-                    &key.as_token(),
-                    scope,
-                )?;
-                annotate_token(
-                    compiler,
-                    functions,
-                    types,
-                    annotated_functions,
-                    current_function,
-                    value,
-                    scope,
-                )?;
-            }
-            // types.add_constraint(Constraint::IsLiteral(type_var.clone(), Type::Map<Type::String, Type::String>));
+            let (key_type, value_type, _) = annotate_map(
+                compiler,
+                functions,
+                types,
+                annotated_functions,
+                current_function,
+                map,
+                type_var,
+                false,
+                scope,
+            )?;
+            // maps are stored natively, which
+            // limits the types they may contain.
+            types.add_constraint(Constraint::HasClass(key_type, map_entry()))?;
+            types.add_constraint(Constraint::HasClass(value_type, map_entry()))?;
+            current_function.map_types.borrow_mut().push((
+                map_address(map),
+                type_var,
+                compiler.data.location.clone(),
+            ));
         }
         Token::Symbol(s) => match scope.get(s) {
             Some(variable) => return Ok(variable),
@@ -302,9 +578,86 @@ fn annotate_token<'a>(
         },
         _ => {}
     }
+    if let Some(previous_origin) = previous_origin {
+        types.set_origin(previous_origin);
+    }
     Ok(type_var)
 }
 
+/// annotate the keys and values of a map, which must have the
+/// type of the first one. The default arm `_` of a match is
+/// not a key, so its value is returned separately.
+fn annotate_map<'a>(
+    compiler: &mut Compiler,
+    functions: &FunctionMap,
+    types: &mut TypeResolver<TypecheckType>,
+    annotated_functions: &mut TypevarFunctionMap,
+    current_function: &TypevarFunction,
+    map: &Map,
+    type_var: TypeVar,
+    has_default_arm: bool,
+    scope: &mut AnnotatorScope<'a>,
+) -> GenericResult<(TypeVar, TypeVar, Option<TypeVar>)> {
+    let key_type = types.create_type_var();
+    let value_type = types.create_type_var();
+    types.add_constraint(Constraint::IsLiteral(
+        type_var,
+        Unresolved::Generic(TypecheckType::Map, vec![key_type, value_type]),
+    ))?;
+    let mut default = None;
+    for (key, value) in map.iter() {
+        let key = if has_default_arm && key.is_wildcard() {
+            None
+        } else {
+            Some(annotate_token(
+                compiler,
+                functions,
+                types,
+                annotated_functions,
+                current_function,
+                &key.as_token(),
+                scope,
+            )?)
+        };
+        let value = annotate_token(
+            compiler,
+            functions,
+            types,
+            annotated_functions,
+            current_function,
+            value,
+            scope,
+        )?;
+        match key {
+            Some(key) => {
+                types.add_constraint(Constraint::Equality(key_type, key))?;
+                types.add_constraint(Constraint::Equality(value_type, value))?;
+            }
+            None => default = Some(value),
+        }
+    }
+    Ok((key_type, value_type, default))
+}
+
+/// the address of a map literal, which identifies
+/// it from type annotation to codegen.
+pub fn map_address(map: &Map) -> usize {
+    map as *const Map as usize
+}
+
+/// the origin of the constraints added for the source,
+/// at the location that is being annotated.
+fn origin(compiler: &Compiler, source: Source) -> Option<Origin> {
+    Some(Origin::new(
+        source,
+        compiler
+            .data
+            .location
+            .as_ref()
+            .map(|location| location.to_string()),
+    ))
+}
+
 fn parse_and_add_expression<'a>(
     compiler: &mut Compiler,
     functions: &FunctionMap,
@@ -335,16 +688,29 @@ fn parse_and_add_expression<'a>(
 
         let arg_type_variables = {
             let mut arg_type_variables = vec![];
-            for token in &expression[1..] {
-                arg_type_variables.push(annotate_token(
-                    compiler,
-                    functions,
-                    types,
-                    annotated_functions,
-                    function,
-                    token,
-                    scope,
-                )?);
+            for (i, token) in expression[1..].iter().enumerate() {
+                match token {
+                    Token::Map(ref arms) if *name == "match" && i == 1 => {
+                        arg_type_variables.extend(annotate_match_arms(
+                            compiler,
+                            functions,
+                            types,
+                            annotated_functions,
+                            function,
+                            arms,
+                            scope,
+                        )?);
+                    }
+                    _ => arg_type_variables.push(annotate_token(
+                        compiler,
+                        functions,
+                        types,
+                        annotated_functions,
+                        function,
+                        token,
+                        scope,
+                    )?),
+                }
             }
             arg_type_variables
         };
@@ -353,56 +719,62 @@ fn parse_and_add_expression<'a>(
         // there is a matching primitive function.
         // TODO: check types in compiler
         if let Some(expression_struct) = compiler.data.builtin_expressions.get(&*name) {
-            return (expression_struct.typecheck)(types, function, &arg_type_variables);
+            let previous_origin =
+                types.set_origin(origin(compiler, Source::Builtin(*name.clone())));
+            let result = (expression_struct.typecheck)(types, function, &arg_type_variables)?;
+            types.set_origin(previous_origin);
+            return Ok(result);
         }
 
         // next, there are builtin native functions that we should check against.
-        // next, we check if there is an already
-        // parsed function that matches the type signature
-        if let Some(ref function) = annotated_functions.get(&*name, arg_type_variables.len()) {
-            return Ok(function.return_type);
+        let function = match functions.get(&*name) {
+            Some(function) => function,
+            None => {
+                return Err(Box::new(DispError::new(&format!(
+                    "unable to find function with name {}",
+                    *name
+                ))))
+            }
+        };
+        if function.args.len() != arg_type_variables.len() {
+            return Err(Box::new(DispError::new(&format!(
+                "function {} expects {} arguments. found {}",
+                name,
+                function.args.len(),
+                arg_type_variables.len()
+            ))));
         }
-        // finally, we check to see if there is an unparsed function with the name
-        // and argument count, and if so we start generating an expression for that.
-        match functions.get(&*name) {
-            None => Err(Box::new(DispError::new(&format!(
-                "unable to find function with name {}",
-                *name
-            )))),
-            Some(ref function) => {
-                let return_type = types.create_type_var();
-                let typevar_function = Rc::new(TypevarFunction::new(
-                    (*function).clone(),
-                    arg_type_variables.clone(),
-                    return_type.clone(),
-                ));
-                // the annotated function must be inserted before parsing the body,
-                // to ensure that recursive definitions to not re-enter this and cause
-                // a recursive loop.
-                annotated_functions.insert(
-                    *name,
-                    arg_type_variables.len(),
-                    typevar_function.clone(),
-                );
-                let mut function_scope = AnnotatorScope::new();
-                for i in 0..arg_type_variables.len() {
-                    function_scope
-                        .locals
-                        .insert(function.args[i].clone(), arg_type_variables[i].clone());
-                }
-                let result = annotate_token(
-                    compiler,
-                    functions,
-                    types,
-                    annotated_functions,
-                    &typevar_function,
-                    &function.body,
-                    &mut function_scope,
-                )?;
-                types.add_constraint(Constraint::Equality(return_type, result))?;
-                Ok(return_type)
+        // next, we check if the function is being annotated. Its types
+        // are not generalized yet, so the call must match them.
+        let call_origin = origin(compiler, Source::Expression(*name.clone()));
+        if let Some(ref function) = annotated_functions.get(&*name) {
+            let previous_origin = types.set_origin(call_origin);
+            for (parameter, argument) in function.arg_types.iter().zip(&arg_type_variables) {
+                types.add_constraint(Constraint::Equality(*parameter, *argument))?;
             }
+            types.set_origin(previous_origin);
+            return Ok(function.return_type);
+        }
+        // calls to functions that failed to type check are not checked.
+        if annotated_functions.failed.contains(&*name) {
+            return Ok(types.create_type_var());
+        }
+        // finally, we annotate the function if that has not happened
+        // yet, and create an instance of it for the call.
+        if !annotated_functions.schemes.contains_key(&*name) {
+            annotate_function(
+                compiler,
+                functions,
+                types,
+                annotated_functions,
+                &name,
+                function,
+            )?;
         }
+        let previous_origin = types.set_origin(call_origin);
+        let result = instantiate_function(types, annotated_functions, &name, &arg_type_variables)?;
+        types.set_origin(previous_origin);
+        Ok(result)
     } else {
         Err(Box::new(DispError::new(&format!(
             "expected symbol as first argument to expression, found {}",
@@ -410,3 +782,211 @@ fn parse_and_add_expression<'a>(
         ))))
     }
 }
+
+/// the arms of a match are a map from the values matched to
+/// the value of each arm. The key of the default arm `_` is not
+/// a value, so the arm is not part of the map. A match that is
+/// exhaustive, with a default arm or both a true and a false arm,
+/// produces a value, whose type is passed to the match as another
+/// argument. Other matches do not produce a value.
+fn annotate_match_arms<'a>(
+    compiler: &mut Compiler,
+    functions: &FunctionMap,
+    types: &mut TypeResolver<TypecheckType>,
+    annotated_functions: &mut TypevarFunctionMap,
+    function: &TypevarFunction,
+    arms: &Map,
+    scope: &mut AnnotatorScope<'a>,
+) -> GenericResult<Vec<TypeVar>> {
+    let arms_type = types.create_type_var();
+    let (key_type, value_type, default) = annotate_map(
+        compiler,
+        functions,
+        types,
+        annotated_functions,
+        function,
+        arms,
+        arms_type,
+        true,
+        scope,
+    )?;
+    if let Some(default) = default {
+        return Ok(vec![arms_type, default]);
+    }
+    if arms.contains_key(&HashableToken::Boolean(true))
+        && arms.contains_key(&HashableToken::Boolean(false))
+    {
+        return Ok(vec![arms_type, value_type]);
+    }
+    match types.get_type(&key_type) {
+        Some(Resolved::Literal(TypecheckType::Bool)) => {
+            return Err(Box::new(DispError::new(
+                "match on Bool is not exhaustive: it needs a true and a false arm, or a default arm `_`",
+            )));
+        }
+        Some(_) => {}
+        // the type of a match on arguments is checked
+        // once the function is called.
+        None => {
+            let previous_origin =
+                types.set_origin(origin(compiler, Source::Builtin(String::from("match"))));
+            types.add_constraint(Constraint::HasClass(key_type, partial_match()))?;
+            types.set_origin(previous_origin);
+        }
+    }
+    Ok(vec![arms_type])
+}
+
+/// annotate the function, and generalize its types.
+fn annotate_function(
+    compiler: &mut Compiler,
+    functions: &FunctionMap,
+    types: &mut TypeResolver<TypecheckType>,
+    annotated_functions: &mut TypevarFunctionMap,
+    name: &String,
+    function: &Rc<UnparsedFunction>,
+) -> GenericResult<()> {
+    let arg_types: Vec<TypeVar> = function
+        .args
+        .iter()
+        .map(|_| types.create_type_var())
+        .collect();
+    let typevar_function = Rc::new(TypevarFunction::new(
+        function.clone(),
+        arg_types.clone(),
+        types.create_type_var(),
+    ));
+    // the annotated function must be inserted before parsing the body,
+    // to ensure that recursive definitions to not re-enter this and cause
+    // a recursive loop.
+    annotated_functions.insert(name.clone(), typevar_function.clone());
+    let mut function_scope = AnnotatorScope::new();
+    for i in 0..arg_types.len() {
+        function_scope
+            .locals
+            .insert(function.args[i].clone(), arg_types[i].clone());
+    }
+    let result = annotate_token(
+        compiler,
+        functions,
+        types,
+        annotated_functions,
+        &typevar_function,
+        &function.body,
+        &mut function_scope,
+    )?;
+    // the last expression of the body is returned, so it must have the
+    // type of every return. If it has no value, a return is missing.
+    if let Err(error) =
+        types.add_constraint(Constraint::Equality(typevar_function.return_type, result))
+    {
+        return Err(Box::new(DispError::new(
+            &match types.get_type(&result) {
+                Some(Resolved::Literal(TypecheckType::None)) => {
+                    format!("missing return at the end of {}: {}", name, error)
+                }
+                _ => error,
+            },
+        )));
+    }
+    check_map_types(compiler, types, annotated_functions, &typevar_function)?;
+    let calls = annotated_functions.remove(name);
+    let mut type_vars = typevar_function.type_vars();
+    for call in &calls {
+        type_vars.extend(call.function.type_vars());
+    }
+    let scheme = types.generalize(&type_vars, &annotated_functions.monomorphic_types());
+    annotated_functions.schemes.insert(
+        name.clone(),
+        FunctionScheme {
+            function: typevar_function,
+            calls,
+            scheme,
+        },
+    );
+    Ok(())
+}
+
+/// check that the types of the map literals in the function are
+/// known, or depend on its argument and return types, which
+/// every call resolves. A map that is only counted, for example,
+/// could contain anything.
+fn check_map_types(
+    compiler: &mut Compiler,
+    types: &TypeResolver<TypecheckType>,
+    annotated_functions: &TypevarFunctionMap,
+    function: &TypevarFunction,
+) -> GenericResult<()> {
+    let mut fixed = function.arg_types.clone();
+    fixed.push(function.return_type);
+    // the other functions that are being annotated may not be generalized.
+    for other in annotated_functions.map.values() {
+        if !ptr::eq(&**other, function) {
+            fixed.extend(other.type_vars());
+        }
+    }
+    for (_, type_var, location) in function.map_types.borrow().iter() {
+        if types.generalize(&[*type_var], &fixed).is_generic() {
+            compiler.data.location = location.clone();
+            return Err(Box::new(DispError::new(&format!(
+                "unable to infer the types of the keys and values of the map, only that it is a {}",
+                types.describe_types(&[*type_var])[0]
+            ))));
+        }
+    }
+    Ok(())
+}
+
+/// create an instance of an annotated function for a call,
+/// returning its return type. The instances of the functions
+/// it calls are created as well.
+fn instantiate_function(
+    types: &mut TypeResolver<TypecheckType>,
+    annotated_functions: &mut TypevarFunctionMap,
+    name: &String,
+    args: &Vec<TypeVar>,
+) -> GenericResult<TypeVar> {
+    let (function, calls, type_vars) = {
+        let scheme = &annotated_functions.schemes[name];
+        (
+            scheme.function.clone(),
+            scheme.calls.clone(),
+            types.instantiate(&scheme.scheme)?,
+        )
+    };
+    let mut type_vars = type_vars.into_iter();
+    let instance = take_instance(&mut type_vars, &function);
+    for (parameter, argument) in instance.arg_types.iter().zip(args) {
+        types.add_constraint(Constraint::Equality(*parameter, *argument))?;
+    }
+    let return_type = instance.return_type;
+    annotated_functions.add_call(FunctionInstance {
+        name: name.clone(),
+        function: Rc::new(instance),
+    });
+    for call in calls {
+        let instance = take_instance(&mut type_vars, &call.function);
+        annotated_functions.add_call(FunctionInstance {
+            name: call.name,
+            function: Rc::new(instance),
+        });
+    }
+    Ok(return_type)
+}
+
+/// build an instance of the function from the next types of the scheme.
+fn take_instance(
+    type_vars: &mut impl Iterator<Item = TypeVar>,
+    function: &TypevarFunction,
+) -> TypevarFunction {
+    let arg_types = type_vars.by_ref().take(function.arg_types.len()).collect();
+    let return_type = type_vars.next().unwrap();
+    let instance = TypevarFunction::new(function.function.clone(), arg_types, return_type);
+    for (map, _, location) in function.map_types.borrow().iter() {
+        instance
+            .map_types
+            .borrow_mut()
+            .push((*map, type_vars.next().unwrap(), location.clone()));
+    }
+    instance
+}
@@ -0,0 +1,448 @@
+use super::super::{link_modules, load_module, parse_file, ExpansionContext, MacroMap};
+use super::{annotate_types, check_types, AnnotatedFunctionMap, Compiler, GenericResult, Type};
+
+fn annotate(source: &str) -> GenericResult<AnnotatedFunctionMap> {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    load_module(&mut context, "main", parse_file(source, "main.ds"))?;
+    annotate_types(&mut Compiler::new(), &link_modules(&context.modules)?)
+}
+
+fn return_type(functions: &AnnotatedFunctionMap, name: &str, arg_types: Vec<Type>) -> Type {
+    functions[name][&arg_types].return_type.clone()
+}
+
+#[test]
+fn test_generic_function_called_with_different_types() {
+    let functions = annotate(
+        "fn id [x] (return x)
+print (id 1)
+print (id \"foo\")",
+    )
+    .unwrap();
+    assert_eq!(return_type(&functions, "id", vec![Type::Int]), Type::Int);
+    assert_eq!(
+        return_type(&functions, "id", vec![Type::String]),
+        Type::String
+    );
+}
+
+#[test]
+fn test_generic_function_calls_generic_function() {
+    let functions = annotate(
+        "fn id [x] (return x)
+fn twice [x] (return (id (id x)))
+print (twice true)
+let pair (twice [1 2])",
+    )
+    .unwrap();
+    let array = Type::Array(Box::new(Type::Int));
+    assert_eq!(
+        return_type(&functions, "twice", vec![Type::Bool]),
+        Type::Bool
+    );
+    assert_eq!(return_type(&functions, "id", vec![Type::Bool]), Type::Bool);
+    assert_eq!(return_type(&functions, "id", vec![array.clone()]), array);
+}
+
+#[test]
+fn test_instance_type_mismatch() {
+    assert!(annotate(
+        "fn inc [x] (return (+ x 1))
+print (inc 1)
+print (inc \"foo\")"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_specialization_per_argument_types() {
+    let functions = annotate(
+        "fn size [value] (return (len value))
+print (size [1 2])
+print (size 'ab')
+print (size [3 4])",
+    )
+    .unwrap();
+    let specializations = &functions["size"];
+    assert_eq!(specializations.len(), 2);
+    assert!(specializations.contains_key(&vec![Type::Array(Box::new(Type::Int))]));
+    assert!(specializations.contains_key(&vec![Type::Array(Box::new(Type::Byte))]));
+}
+
+fn check(source: &str) -> Vec<(String, Result<String, String>)> {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    load_module(&mut context, "main", parse_file(source, "main.ds")).unwrap();
+    let functions = link_modules(&context.modules).unwrap();
+    check_types(&mut Compiler::new(), &functions)
+        .into_iter()
+        .map(|check| {
+            (
+                check.function,
+                check.result.map_err(|error| format!("{}", error)),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_check_functions_that_are_not_called() {
+    let checks = check(
+        "fn id [x] (return x)
+fn inc [x] (return (+ x 1))
+fn pair [l r] (return [l r])",
+    );
+    assert_eq!(
+        checks,
+        vec![
+            (String::from("id"), Ok(String::from("[a] -> a"))),
+            (String::from("inc"), Ok(String::from("[Int] -> Int"))),
+            (String::from("main"), Ok(String::from("[] -> None"))),
+            (String::from("pair"), Ok(String::from("[a a] -> Array<a>"))),
+        ]
+    );
+}
+
+#[test]
+fn test_check_functions_with_main_in_their_name() {
+    let checks = check(
+        "fn remainder [a b] (mod a b)
+fn domain [x] (+ x 1)",
+    );
+    assert_eq!(
+        checks,
+        vec![
+            (String::from("domain"), Ok(String::from("[Int] -> Int"))),
+            (String::from("main"), Ok(String::from("[] -> None"))),
+            (
+                String::from("remainder"),
+                Ok(String::from("[a a] -> a where a: Multiply"))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_check_reports_every_error() {
+    let checks = check(
+        "fn first [x] (return (+ x true))
+fn second [x] (return (eq (+ x 1) \"foo\"))
+fn caller [x] (return (first x))",
+    );
+    let failed: Vec<&String> = checks
+        .iter()
+        .filter(|(_, result)| result.is_err())
+        .map(|(name, _)| name)
+        .collect();
+    // caller is not reported, as the error is in first.
+    assert_eq!(failed, vec!["first", "second"]);
+}
+
+#[test]
+fn test_type_error_explains_both_types() {
+    let error = annotate(
+        "let x (eq 1 2)
+print (not 1)",
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected Bool because of `not` at main.ds:2, found Int from `1` at main.ds:2"
+    );
+}
+
+#[test]
+fn test_type_classes() {
+    let error = annotate(
+        "let x (eq 1 2)
+print (+ x 1)",
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected a type that supports Add because of `+` at main.ds:2, found Bool from `eq` at main.ds:1"
+    );
+    assert!(annotate("print (eq \"foo\" \"bar\")").is_err());
+    assert!(annotate("print [1 2]").is_err());
+    assert!(annotate("print (+ 1 true)").is_err());
+    let checks = check(
+        "fn add [l r] (return (+ l r))
+fn shift-sum [a b c] (return (shl (+ a b) c))
+print (add 1 2)",
+    );
+    assert_eq!(
+        checks[0],
+        (
+            String::from("add"),
+            Ok(String::from("[a a] -> a where a: Add"))
+        )
+    );
+    assert_eq!(
+        checks[2],
+        (
+            String::from("shift-sum"),
+            Ok(String::from("[a a a] -> a where a: Add + Shift"))
+        )
+    );
+}
+
+#[test]
+fn test_operators() {
+    let functions = annotate(
+        "let x (* (/ 7 2) (mod 7 2))
+let y (shl (u8 1) (u8 2))
+let smaller (< (- 1.5) 2.0)
+print (and (neq x 1) smaller)",
+    )
+    .unwrap();
+    assert_eq!(return_type(&functions, "main", vec![]), Type::None);
+    let error = annotate("print (< true false)").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected a type that supports Ord because of `<` at main.ds:1, found Bool from `true` at main.ds:1"
+    );
+    assert!(annotate("print (shl 1.0 2.0)").is_err());
+    assert!(annotate("print (* 2 1.5)").is_err());
+    // only - has a single argument form.
+    let error = annotate("print (+ 1 2 3)").unwrap_err();
+    assert_eq!(error.to_string(), "expected 2 arguments, found 3");
+    assert!(annotate("print (* 2)").is_err());
+    assert!(annotate("print (and true false true)").is_err());
+    assert!(annotate("print (- 1)").is_ok());
+}
+
+#[test]
+fn test_match_arms_have_the_same_type() {
+    let checks = check(
+        "fn describe [n limit]
+\tmatch n {
+\t\t1: \"one\",
+\t\tlimit: \"the limit\",
+\t\t_: \"many\",
+\t}
+print (describe 1 2)",
+    );
+    assert_eq!(
+        checks[0],
+        (
+            String::from("describe"),
+            Ok(String::from("[Int Int] -> String"))
+        )
+    );
+    let error = annotate("print (match 1 {1: 1, _: \"no\",})").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected Int because of `1` at main.ds:1, found String from `\"no\"` at main.ds:1"
+    );
+}
+
+#[test]
+fn test_match_exhaustiveness() {
+    let checks = check(
+        "fn sign [x]
+\tmatch (< x 0) {
+\t\ttrue: (return (- 1)),
+\t\tfalse: (return 1),
+\t}
+fn first [x] (match x {true: (print 1),})
+fn either [x y] (match x {y: (print 1),})",
+    );
+    assert_eq!(
+        checks[0],
+        (
+            String::from("either"),
+            Ok(String::from("[a a] -> None where a: PartialMatch + Eq"))
+        )
+    );
+    assert_eq!(
+        checks[1],
+        (
+            String::from("first"),
+            Err(String::from(
+                "match on Bool is not exhaustive: it needs a true and a false arm, or a default arm `_`"
+            ))
+        )
+    );
+    assert_eq!(
+        checks[3],
+        (String::from("sign"), Ok(String::from("[Int] -> Int")))
+    );
+    // a match without a default arm has no value.
+    assert!(annotate("let x (match 1 {1: 5,})\nprint (+ x 1)").is_err());
+}
+
+#[test]
+fn test_conditional_branches_have_the_same_type() {
+    let checks = check(
+        "fn sign [n]
+\tcond (< n 0) \"negative\" (eq n 0) \"zero\" \"positive\"
+fn fact [n] (if (<= n 1) 1 (* n (fact (- n 1))))
+print (sign (fact 3))",
+    );
+    assert_eq!(
+        checks[0],
+        (String::from("fact"), Ok(String::from("[Int] -> Int")))
+    );
+    assert_eq!(
+        checks[2],
+        (String::from("sign"), Ok(String::from("[Int] -> String")))
+    );
+    let error = annotate("print (if true 1 \"no\")").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected String because of `\"no\"` at main.ds:1, found Int from `1` at main.ds:1"
+    );
+    assert!(annotate("if 1 (print 1)").is_err());
+    assert!(annotate("let x (if true 1)\nprint (+ x 1)").is_err());
+}
+
+#[test]
+fn test_infinite_type() {
+    let error = annotate(
+        "fn wrap [x] (return (wrap [x]))
+print (wrap 1)",
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "infinite type a = Array<a> from `wrap` at main.ds:1"
+    );
+}
+
+#[test]
+fn test_map_types() {
+    let checks = check(
+        "fn make [k v]
+\tlet m {}
+\tinsert m k v
+\treturn m
+fn size [m] (return (count m))",
+    );
+    assert_eq!(
+        checks[0],
+        (String::from("main"), Ok(String::from("[] -> None")))
+    );
+    assert_eq!(
+        checks[1],
+        (
+            String::from("make"),
+            Ok(String::from(
+                "[a b] -> Map<a, b> where a: MapEntry, b: MapEntry"
+            ))
+        )
+    );
+    assert_eq!(
+        checks[2],
+        (String::from("size"), Ok(String::from("[Map<a, b>] -> Int")))
+    );
+    let functions = annotate(
+        "let m {1: true,}
+insert m 2 false
+print (count m)",
+    )
+    .unwrap();
+    assert!(functions.contains_key("main"));
+    assert!(annotate(
+        "let m {1: true,}
+insert m 2 3"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_empty_map_types_are_inferred() {
+    let functions = annotate(
+        "fn fill [m] (insert m 1 true)
+let m {}
+fill m
+print (count m)",
+    )
+    .unwrap();
+    let map_types: Vec<&Type> = functions["main"][&vec![]].map_types.values().collect();
+    assert_eq!(
+        map_types,
+        vec![&Type::Map(Box::new(Type::Int), Box::new(Type::Bool))]
+    );
+    let error = annotate("let m {1: 1.5,}").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "expected a type that supports MapEntry because of `{1: 1.5,}` at main.ds:1, found Float from `1.5` at main.ds:1"
+    );
+    assert!(annotate("let m {}\ninsert m 1 1.5").is_err());
+    let error = annotate("let m {}\nprint (count m)").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unable to infer the types of the keys and values of the map, only that it is a Map<a, b>"
+    );
+}
+
+#[test]
+fn test_float_types() {
+    let checks = check(
+        "fn mean [l r] (return (- (+ l r) (float 1)))
+print (mean 1.5 (float \"2\"))
+print (int 2.5)",
+    );
+    assert_eq!(
+        checks[1],
+        (
+            String::from("mean"),
+            Ok(String::from("[Float Float] -> Float"))
+        )
+    );
+    assert!(annotate("print (+ 1 1.5)").is_err());
+    assert!(annotate("print (int true)").is_err());
+}
+
+#[test]
+fn test_sized_integer_types() {
+    let checks = check(
+        "fn add [l r] (return (+ l r))
+print (add (u8 1) (u8 2))
+print (int (add (i32 1) (i32 2)))",
+    );
+    assert_eq!(
+        checks[0],
+        (
+            String::from("add"),
+            Ok(String::from("[a a] -> a where a: Add"))
+        )
+    );
+    assert!(annotate("print (+ (i8 1) 1)").is_err());
+    assert!(annotate("print (u8 \"1\")").is_err());
+}
+
+#[test]
+fn test_last_expression_is_returned() {
+    let checks = check(
+        "fn inc [x] (+ x 1)
+fn sign [x]
+\twhile (not (eq x 0))
+\t\treturn 1
+\treturn 0
+fn loop [x]
+\twhile (eq x 1)
+\t\treturn 1
+fn pick [x] (if x (return 1) (return 2))",
+    );
+    assert_eq!(
+        checks[0],
+        (String::from("inc"), Ok(String::from("[Int] -> Int")))
+    );
+    assert_eq!(
+        checks[1],
+        (
+            String::from("loop"),
+            Err(String::from(
+                "missing return at the end of loop: expected Int because of `1` at main.ds:8, found None from `while` at main.ds:7"
+            ))
+        )
+    );
+    assert_eq!(
+        checks[3],
+        (String::from("pick"), Ok(String::from("[Bool] -> Int")))
+    );
+    assert_eq!(
+        checks[4],
+        (String::from("sign"), Ok(String::from("[Int] -> Int")))
+    );
+}
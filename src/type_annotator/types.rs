@@ -8,9 +8,17 @@ pub enum TypecheckType {
     Array,
     Bool,
     Byte,
+    Float,
     Int,
+    I8,
+    I16,
+    I32,
+    U8,
+    U64,
+    Map,
     None,
-    String
+    String,
+    Token,
 }
 
 pub fn to_type(resolved_type: &Resolved<TypecheckType>) ->  DispResult<Type> {
@@ -18,9 +26,16 @@ pub fn to_type(resolved_type: &Resolved<TypecheckType>) ->  DispResult<Type> {
         Resolved::Literal(ref literal) => match literal {
             &TypecheckType::Bool => Ok(Type::Bool),
             &TypecheckType::Byte => Ok(Type::Byte),
+            &TypecheckType::Float => Ok(Type::Float),
             &TypecheckType::Int => Ok(Type::Int),
+            &TypecheckType::I8 => Ok(Type::I8),
+            &TypecheckType::I16 => Ok(Type::I16),
+            &TypecheckType::I32 => Ok(Type::I32),
+            &TypecheckType::U8 => Ok(Type::U8),
+            &TypecheckType::U64 => Ok(Type::U64),
             &TypecheckType::None => Ok(Type::None),
             &TypecheckType::String => Ok(Type::String),
+            &TypecheckType::Token => Ok(Type::Token),
             _ => Err(DispError::new(&format!("invalid resolved type {:?}", resolved_type)))
         },
         Resolved::Generic(ref generic, ref subtypes) => match generic {
@@ -28,6 +43,11 @@ pub fn to_type(resolved_type: &Resolved<TypecheckType>) ->  DispResult<Type> {
                 let subtype = to_type(&subtypes[0])?;
                 Ok(Type::Array(Box::new(subtype)))
             },
+            &TypecheckType::Map => {
+                let key = to_type(&subtypes[0])?;
+                let value = to_type(&subtypes[1])?;
+                Ok(Type::Map(Box::new(key), Box::new(value)))
+            },
             _ => Err(DispError::new(&format!("invalid resolved type {:?}", resolved_type)))
         }
     }
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::fmt;
 
 /// The type enum is used to define types for Disp's
 /// type checker.
@@ -7,11 +8,32 @@ pub enum Type {
     Array(Box<Type>),
     Bool,
     Byte,
+    Float,
     FunctionPrototype,
     Int,
+    /// integers of a fixed size, which are converted
+    /// from and to other integers explicitly.
+    I8,
+    I16,
+    I32,
+    U8,
+    U64,
     None,
     String,
     Map(Box<Type>, Box<Type>),
+    /// a syntax token. Used by procedural
+    /// macros to inspect and build code.
+    Token,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Array(ref t) => write!(f, "Array<{}>", t),
+            Type::Map(ref k, ref v) => write!(f, "Map<{}, {}>", k, v),
+            t => write!(f, "{:?}", t),
+        }
+    }
 }
 
 /// A map that contains all created types.
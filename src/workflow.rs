@@ -1,35 +1,44 @@
 use super::{
-    annotate_types, apply_macros_to_function_map, build_functions, parse,
-    parse_functions_and_macros, Builder, Compiler, GenericResult,
+    annotate_types, build_functions, check_types, link_modules, load_module, Builder, Compiler,
+    DispError, ExpansionContext, FunctionMap, GenericError, GenericResult, Location, MacroMap,
+    Token, STDLIB_MODULE,
 };
 use std::time::Instant;
 
 /// runs through the workflow as described
 /// in compiler-design.
-pub fn load_string_into_compiler(compiler: &mut Compiler, input: &str) -> GenericResult<()> {
-    let token = parse(input);
+pub fn load_program_into_compiler(
+    compiler: &mut Compiler,
+    program: Token,
+    max_expansion_depth: usize,
+) -> GenericResult<()> {
     if cfg!(feature = "debug") {
         println!("parsing functions...")
     }
-    let (mut functions, macros) = parse_functions_and_macros(compiler, token)?;
+    let mut context = ExpansionContext::new(MacroMap::new());
+    context.max_depth = max_expansion_depth;
+    let functions = expand_functions(&mut context, program)?;
     if cfg!(feature = "debug") {
         println!(
-            "applying macros {:?} to functions: {:?}...",
-            &macros, &functions
+            "applied macros {:?} to functions: {:?}...",
+            &context.macros, &functions
         );
     }
-    apply_macros_to_function_map(&macros, &mut functions)?;
     if cfg!(feature = "debug") {
         println!(
             "applying annotating types for functions: {:?}...",
             &functions.keys()
         );
     }
-    let annotated_functions = annotate_types(compiler, &functions)?;
+    let annotated_functions = with_location(
+        annotate_types(compiler, &functions),
+        &compiler.data.location,
+    )?;
     if cfg!(feature = "debug") {
         println!("building functions: {:?}...", &annotated_functions.keys());
     }
-    build_functions(compiler, &annotated_functions)?;
+    let result = build_functions(compiler, &annotated_functions);
+    with_location(result, &compiler.data.location)?;
     let mut builder = Builder::new(&compiler.llvm);
     builder.build(&compiler.data, &mut compiler.llvm.types);
     let f = builder.get_function("main")?;
@@ -42,3 +51,85 @@ pub fn load_string_into_compiler(compiler: &mut Compiler, input: &str) -> Generi
     }
     Ok(())
 }
+
+/// expand all macros in the program, without compiling
+/// the result. Used to inspect the output of macros.
+pub fn expand_program(
+    program: Token,
+    record_steps: bool,
+    max_expansion_depth: usize,
+) -> GenericResult<(FunctionMap, ExpansionContext)> {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    context.record_steps = record_steps;
+    context.max_depth = max_expansion_depth;
+    let functions = expand_functions(&mut context, program)?;
+    Ok((functions, context))
+}
+
+/// type check every function of the program, without running
+/// it. Returns the module and the name of every function, with
+/// its signature or the error it failed with.
+pub fn check_program(
+    program: Token,
+    max_expansion_depth: usize,
+) -> GenericResult<Vec<(String, String, GenericResult<String>)>> {
+    let mut context = ExpansionContext::new(MacroMap::new());
+    context.max_depth = max_expansion_depth;
+    let functions = expand_functions(&mut context, program)?;
+    let mut checks = vec![];
+    for check in check_types(&mut Compiler::new(), &functions) {
+        // the top-level statements of every module
+        // are linked into the main function.
+        let module = match context
+            .modules
+            .iter()
+            .find(|module| module.functions.contains_key(&check.function))
+        {
+            Some(module) if check.function != "main" => module.name.clone(),
+            _ => String::from("main"),
+        };
+        // the functions of the standard library
+        // are checked, but not reported.
+        if module == STDLIB_MODULE {
+            continue;
+        }
+        checks.push((
+            module,
+            check.function,
+            with_location(check.result, &check.location),
+        ));
+    }
+    checks.sort_by(|(l_module, l_name, _), (r_module, r_name, _)| {
+        (l_module, l_name).cmp(&(r_module, r_name))
+    });
+    Ok(checks)
+}
+
+/// load the program as the main module, along with the
+/// modules it imports, and expand all macros within
+/// their functions.
+fn expand_functions(context: &mut ExpansionContext, program: Token) -> GenericResult<FunctionMap> {
+    context.prelude = Some(String::from(STDLIB_MODULE));
+    let result = load_module(context, "main", program);
+    with_location(result, &context.current_location)?;
+    Ok(link_modules(&context.modules)?)
+}
+
+/// add the location an error occurred at to the error,
+/// along with the macro expansions that produced it.
+fn with_location<T, E: Into<GenericError>>(
+    result: Result<T, E>,
+    location: &Option<Location>,
+) -> GenericResult<T> {
+    result.map_err(|error| {
+        let error = error.into();
+        match location {
+            Some(location) => Box::new(DispError::new(&format!(
+                "{}\n  {}",
+                error,
+                location.describe().replace("\n", "\n  ")
+            ))) as GenericError,
+            None => error,
+        }
+    })
+}